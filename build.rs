@@ -1,4 +1,8 @@
 fn main() {
+    // the icon and manifest only go into Windows executables
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("windows") {
+        return;
+    }
     embed_resource::compile("assets/app.rc", embed_resource::NONE)
         .manifest_optional()
         .expect("Failed to compile resource file");
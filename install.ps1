@@ -71,6 +71,7 @@ if (Test-Path $outfile) {
 }
 
 Move-Item "$temp\$command.exe" $outfile
+Move-Item -Force "$temp\$command-cli.exe" "$dest\$command-cli.exe"
 
 Remove-Item -Force "$temp.zip"
 Remove-Item -Force -Recurse "$temp"
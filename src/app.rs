@@ -1,27 +1,32 @@
-use crate::config::{edit_config_file, Config};
-use crate::foreground::ForegroundWatcher;
-use crate::keyboard::KeyboardListener;
-use crate::painter::GdiAAPainter;
+use crate::config::{
+    edit_config_file, format_config_issues, get_config_path, load_config, Config, WindowAction,
+};
+use crate::config_watcher::ConfigWatcher;
+use crate::foreground::{ForegroundWatcher, MRU_HISTORY};
+use crate::ipc::{self, Command};
+use crate::ipc_server::{IpcRequest, IpcServer};
+use crate::keyboard::{Direction, KeyboardListener};
+use crate::painter::{ClickedItem, GdiAAPainter};
 use crate::startup::Startup;
+use crate::switcher::Switcher;
 use crate::trayicon::TrayIcon;
 use crate::utils::{
-    check_error, get_app_icon, get_foreground_window, get_window_user_data, is_iconic_window,
-    is_running_as_admin, list_windows, set_foreground_window, set_window_user_data,
+    check_error, get_window_user_data, is_running_as_admin, set_window_user_data, Win32WindowSystem,
 };
 
-use anyhow::{anyhow, Result};
-use indexmap::IndexSet;
-use std::collections::HashMap;
+use anyhow::{anyhow, bail, Result};
+use serde_json::{json, Value};
+use std::sync::Arc;
 use windows::core::{w, PCWSTR};
 use windows::Win32::{
     Foundation::{GetLastError, HINSTANCE, HWND, LPARAM, LRESULT, WPARAM},
     System::LibraryLoader::GetModuleHandleW,
     UI::WindowsAndMessaging::{
-        CreateWindowExW, DefWindowProcW, DestroyIcon, DispatchMessageW, GetMessageW,
-        GetWindowLongPtrW, LoadCursorW, PostMessageW, PostQuitMessage, RegisterClassW,
-        RegisterWindowMessageW, SetWindowLongPtrW, TranslateMessage, CS_HREDRAW, CS_VREDRAW,
-        CW_USEDEFAULT, GWL_STYLE, HICON, HTCLIENT, IDC_ARROW, MSG, WINDOW_STYLE, WM_COMMAND,
-        WM_ERASEBKGND, WM_LBUTTONUP, WM_NCHITTEST, WM_RBUTTONUP, WNDCLASSW, WS_CAPTION,
+        CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, GetWindowLongPtrW,
+        KillTimer, LoadCursorW, PostMessageW, PostQuitMessage, RegisterClassW,
+        RegisterWindowMessageW, SetTimer, SetWindowLongPtrW, TranslateMessage, CS_HREDRAW,
+        CS_VREDRAW, CW_USEDEFAULT, GWL_STYLE, HTCLIENT, IDC_ARROW, MSG, WINDOW_STYLE, WM_COMMAND,
+        WM_ERASEBKGND, WM_LBUTTONUP, WM_NCHITTEST, WM_RBUTTONUP, WM_TIMER, WNDCLASSW, WS_CAPTION,
         WS_EX_LAYERED, WS_EX_TOOLWINDOW, WS_EX_TOPMOST,
     },
 };
@@ -32,12 +37,24 @@ pub const WM_USER_REGISTER_TRAYICON: u32 = 6001;
 pub const WM_USER_SWITCH_APPS: u32 = 6010;
 pub const WM_USER_SWITCH_APPS_DONE: u32 = 6011;
 pub const WM_USER_SWITCH_APPS_CANCEL: u32 = 6012;
+pub const WM_USER_SWITCH_APPS_SEARCH: u32 = 6013;
+pub const WM_USER_SWITCH_APPS_SEARCH_BACKSPACE: u32 = 6014;
+pub const WM_USER_SWITCH_APPS_NAVIGATE: u32 = 6015;
+pub const WM_USER_SWITCH_APPS_WINDOW_ACTION: u32 = 6016;
 pub const WM_USER_SWITCH_WINDOWS: u32 = 6020;
 pub const WM_USER_SWITCH_WINDOWS_DONE: u32 = 6021;
+pub const WM_USER_SWITCH_ALL_WINDOWS: u32 = 6022;
+pub const WM_USER_SWITCH_ALL_WINDOWS_DONE: u32 = 6023;
+pub const WM_USER_RELOAD_CONFIG: u32 = 6030;
+pub const WM_USER_IPC: u32 = 6040;
+pub const WM_USER_LAUNCH: u32 = 6050;
 pub const IDM_EXIT: u32 = 1;
 pub const IDM_STARTUP: u32 = 2;
 pub const IDM_CONFIGURE: u32 = 3;
 
+/// Shows the app switcher once `show_delay` has passed since it was opened.
+const SHOW_SWITCHER_TIMER_ID: usize = 1;
+
 pub fn start(config: &Config) -> Result<()> {
     info!("start config={config:?}");
     App::start(config)
@@ -48,23 +65,35 @@ static mut WM_TASKBARCREATED: u32 = 0;
 
 pub struct App {
     hwnd: HWND,
-    is_admin: bool,
+    keyboard_listener: KeyboardListener,
+    foreground_watcher: ForegroundWatcher,
+    _config_watcher: Option<ConfigWatcher>,
+    _ipc_server: Option<IpcServer>,
     trayicon: Option<TrayIcon>,
     startup: Startup,
-    config: Config,
-    switch_windows_state: SwitchWindowsState,
-    switch_apps_state: Option<SwitchAppsState>,
-    cached_icons: HashMap<String, HICON>,
+    switcher: Switcher<Win32WindowSystem>,
     painter: GdiAAPainter,
+    /// The app switcher is open but waits for the show delay before it is painted.
+    is_show_pending: bool,
 }
 
 impl App {
     pub fn start(config: &Config) -> Result<()> {
         let hwnd = Self::create_window()?;
-        let painter = GdiAAPainter::new(hwnd)?;
+        let painter = GdiAAPainter::new(hwnd, config)?;
 
-        let _foreground_watcher = ForegroundWatcher::init(&config.switch_windows_blacklist)?;
-        let _keyboard_listener = KeyboardListener::init(hwnd, &config.to_hotkeys())?;
+        let foreground_watcher = ForegroundWatcher::init(&config.switch_windows_blacklist)?;
+        let keyboard_listener = KeyboardListener::init(
+            hwnd,
+            &config.to_hotkeys(),
+            &config.switch_apps_action_keys,
+            config.switch_windows_all_overlay,
+        )?;
+        let config_watcher = get_config_path()
+            .and_then(|path| ConfigWatcher::init(hwnd, &path))
+            .map_err(|err| error!("{err}"))
+            .ok();
+        let ipc_server = IpcServer::init(hwnd).map_err(|err| error!("{err}")).ok();
 
         let trayicon = match config.trayicon {
             true => Some(TrayIcon::create()),
@@ -78,17 +107,15 @@ impl App {
 
         let mut app = App {
             hwnd,
-            is_admin,
+            keyboard_listener,
+            foreground_watcher,
+            _config_watcher: config_watcher,
+            _ipc_server: ipc_server,
             trayicon,
             startup,
-            config: config.clone(),
-            switch_windows_state: SwitchWindowsState {
-                cache: None,
-                modifier_released: true,
-            },
-            switch_apps_state: None,
-            cached_icons: Default::default(),
+            switcher: Switcher::new(Win32WindowSystem, config.clone(), is_admin, &MRU_HISTORY),
             painter,
+            is_show_pending: false,
         };
 
         app.set_trayicon();
@@ -191,6 +218,117 @@ impl App {
         }
     }
 
+    fn reload_config(&mut self) {
+        if let Err(err) = self.try_reload_config() {
+            error!("{err}");
+            alert!("{err}");
+        }
+    }
+
+    /// Loads and applies the config file. Like at startup, settings with problems use their
+    /// defaults and the problems are returned as the error.
+    fn try_reload_config(&mut self) -> Result<()> {
+        let (config, issues) = match load_config() {
+            Ok(v) => v,
+            Err(err) => bail!("Failed to reload config, the current config is kept.\n\n{err}"),
+        };
+        if &config != self.switcher.config() {
+            info!("reload config={config:?}");
+            self.apply_config(config).map_err(|err| {
+                anyhow!("Failed to apply config, the current config is kept.\n\n{err}")
+            })?;
+        }
+        if !issues.is_empty() {
+            let issues = format_config_issues(&issues);
+            bail!("Found problems in the config file, the affected settings use their defaults.\n\n{issues}");
+        }
+        Ok(())
+    }
+
+    /// Runs a command of the IPC server. The switchers are not shown, as no modifier
+    /// key is held to release, so the next app or window is activated at once.
+    fn run_command(&mut self, command: Command) -> Result<Option<Value>> {
+        match command {
+            Command::SwitchApps { reverse } => {
+                self.switcher.switch_apps(reverse)?;
+                self.do_switch_app();
+            }
+            Command::SwitchWindows { reverse } => {
+                self.cancel_switch_app();
+                self.switcher.switch_windows(reverse)?;
+                self.switcher.switch_windows_done();
+            }
+            Command::ShowApps => {
+                if self.switcher.switch_apps_state().is_none() {
+                    self.switcher.switch_apps(false)?;
+                    self.paint();
+                    self.keyboard_listener.set_switcher_pinned(true);
+                }
+            }
+            Command::ListWindows => {
+                let windows = self.switcher.list_all_windows()?;
+                return Ok(Some(ipc::windows_data(&windows)));
+            }
+            Command::ReloadConfig => self.try_reload_config()?,
+            Command::Startup { enable } => {
+                if let Some(enable) = enable {
+                    if enable != self.startup.is_enable {
+                        self.startup.toggle()?;
+                    }
+                    if enable != self.startup.is_enable {
+                        bail!("Failed to change startup");
+                    }
+                }
+                return Ok(Some(json!({ "enabled": self.startup.is_enable })));
+            }
+            Command::Quit => {
+                // after the response is sent, exiting stops the IPC server
+                unsafe {
+                    PostMessageW(
+                        Some(self.hwnd),
+                        WM_COMMAND,
+                        WPARAM(IDM_EXIT as _),
+                        LPARAM(0),
+                    )
+                }?;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Swaps in the hooks for the new config, the old ones stay in place if any of them fails.
+    fn apply_config(&mut self, config: Config) -> Result<()> {
+        let foreground_watcher = ForegroundWatcher::init(&config.switch_windows_blacklist)?;
+        let keyboard_listener = match KeyboardListener::init(
+            self.hwnd,
+            &config.to_hotkeys(),
+            &config.switch_apps_action_keys,
+            config.switch_windows_all_overlay,
+        ) {
+            Ok(v) => v,
+            Err(err) => {
+                ForegroundWatcher::set_blacklist(&self.switcher.config().switch_windows_blacklist);
+                return Err(err);
+            }
+        };
+        self.foreground_watcher = foreground_watcher;
+        self.keyboard_listener = keyboard_listener;
+
+        self.cancel_switch_app();
+        match (config.trayicon, self.trayicon.is_some()) {
+            (true, false) => {
+                self.trayicon = Some(TrayIcon::create());
+                self.set_trayicon();
+            }
+            (false, true) => self.trayicon = None,
+            _ => {}
+        }
+        log::set_max_level(config.log_level);
+        self.painter.set_config(&config);
+        self.switcher.set_config(config);
+        Ok(())
+    }
+
     unsafe extern "system" fn window_proc(
         hwnd: HWND,
         msg: u32,
@@ -222,9 +360,19 @@ impl App {
                 debug!("message WM_USER_SWITCH_APPS");
                 let app = get_app(hwnd)?;
                 let reverse = lparam.0 == 1;
-                app.switch_apps(reverse)?;
-                if let Some(state) = &app.switch_apps_state {
-                    app.painter.paint(state);
+                let is_opening = app.switcher.switch_apps_state().is_none();
+                app.switcher.switch_apps(reverse)?;
+                let delay = app.switcher.config().switch_apps_show_delay;
+                let is_delayed = is_opening
+                    && delay > 0
+                    && app.switcher.switch_apps_state().is_some()
+                    // without the timer the switcher would never show, so show it right away
+                    && unsafe { SetTimer(Some(hwnd), SHOW_SWITCHER_TIMER_ID, delay, None) } != 0;
+                if is_delayed {
+                    app.is_show_pending = true;
+                    app.keyboard_listener.set_switcher_hidden(true);
+                } else {
+                    app.paint();
                 }
             }
             WM_USER_SWITCH_APPS_DONE => {
@@ -237,22 +385,92 @@ impl App {
                 let app = get_app(hwnd)?;
                 app.cancel_switch_app();
             }
+            WM_USER_SWITCH_APPS_SEARCH => {
+                debug!("message WM_USER_SWITCH_APPS_SEARCH");
+                let app = get_app(hwnd)?;
+                if let Some(c) = char::from_u32(lparam.0 as u32) {
+                    app.switcher.search_input(c);
+                }
+                app.paint();
+            }
+            WM_USER_SWITCH_APPS_SEARCH_BACKSPACE => {
+                debug!("message WM_USER_SWITCH_APPS_SEARCH_BACKSPACE");
+                let app = get_app(hwnd)?;
+                app.switcher.search_backspace();
+                app.paint();
+            }
+            WM_USER_SWITCH_APPS_NAVIGATE => {
+                debug!("message WM_USER_SWITCH_APPS_NAVIGATE");
+                let app = get_app(hwnd)?;
+                if let Some(direction) = Direction::ALL.get(lparam.0 as usize) {
+                    app.switcher.navigate(*direction)?;
+                }
+                app.paint();
+            }
+            WM_USER_SWITCH_APPS_WINDOW_ACTION => {
+                debug!("message WM_USER_SWITCH_APPS_WINDOW_ACTION");
+                let app = get_app(hwnd)?;
+                if let Some(action) = WindowAction::ALL.get(lparam.0 as usize) {
+                    app.switcher.window_action(*action)?;
+                }
+                app.paint();
+            }
             WM_USER_SWITCH_WINDOWS => {
                 debug!("message WM_USER_SWITCH_WINDOWS");
                 let app = get_app(hwnd)?;
                 let reverse = lparam.0 == 1;
-                let hwnd = app
-                    .switch_apps_state
-                    .as_ref()
-                    .and_then(|state| state.apps.get(state.index).map(|(_, id)| *id))
-                    .unwrap_or_else(get_foreground_window);
-                app.switch_windows(hwnd, reverse)?;
+                app.switcher.switch_windows(reverse)?;
                 app.cancel_switch_app();
             }
             WM_USER_SWITCH_WINDOWS_DONE => {
                 debug!("message WM_USER_SWITCH_WINDOWS_DONE");
                 let app = get_app(hwnd)?;
-                app.switch_windows_state.modifier_released = true;
+                app.switcher.switch_windows_done();
+            }
+            WM_USER_SWITCH_ALL_WINDOWS => {
+                debug!("message WM_USER_SWITCH_ALL_WINDOWS");
+                let app = get_app(hwnd)?;
+                let reverse = lparam.0 == 1;
+                app.switcher.switch_all_windows(reverse)?;
+                if app.switcher.config().switch_windows_all_overlay {
+                    app.paint();
+                } else {
+                    app.cancel_switch_app();
+                }
+            }
+            WM_USER_SWITCH_ALL_WINDOWS_DONE => {
+                debug!("message WM_USER_SWITCH_ALL_WINDOWS_DONE");
+                let app = get_app(hwnd)?;
+                if app.switcher.config().switch_windows_all_overlay {
+                    app.do_switch_app();
+                } else {
+                    app.switcher.switch_windows_done();
+                }
+            }
+            WM_USER_LAUNCH => {
+                debug!("message WM_USER_LAUNCH");
+                let app = get_app(hwnd)?;
+                app.cancel_switch_app();
+                app.switcher.launch(lparam.0 as usize)?;
+            }
+            WM_USER_IPC => {
+                debug!("message WM_USER_IPC");
+                let request = unsafe { Arc::from_raw(lparam.0 as *const IpcRequest) };
+                let app = get_app(hwnd)?;
+                let response = app.run_command(request.command);
+                *request.response.lock() = Some(response);
+                return Ok(LRESULT(0));
+            }
+            WM_USER_RELOAD_CONFIG => {
+                debug!("message WM_USER_RELOAD_CONFIG");
+                let app = get_app(hwnd)?;
+                app.reload_config();
+            }
+            WM_TIMER if wparam.0 == SHOW_SWITCHER_TIMER_ID => {
+                debug!("message WM_TIMER show switcher");
+                let app = get_app(hwnd)?;
+                app.paint();
+                return Ok(LRESULT(0));
             }
             WM_NCHITTEST => {
                 return Ok(LRESULT(HTCLIENT as _));
@@ -298,183 +516,48 @@ impl App {
         Ok(unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) })
     }
 
-    fn switch_windows(&mut self, hwnd: HWND, reverse: bool) -> Result<bool> {
-        let windows = list_windows(
-            self.config.switch_windows_ignore_minimal,
-            self.config.switch_windows_only_current_desktop(),
-            self.is_admin,
-        )?;
-        debug!(
-            "switch windows: hwnd:{hwnd:?} reverse:{reverse} state:{:?}",
-            self.switch_windows_state
-        );
-        let module_path = match windows
-            .iter()
-            .find(|(_, v)| v.iter().any(|(id, _)| *id == hwnd))
-            .map(|(k, _)| k.clone())
-        {
-            Some(v) => v,
-            None => return Ok(false),
-        };
-        match windows.get(&module_path) {
-            None => Ok(false),
-            Some(windows) => {
-                let windows_len = windows.len();
-                if windows_len == 1 {
-                    return Ok(false);
-                }
-                let current_id = windows[0].0;
-                let mut index = 1;
-                let mut state_id = current_id;
-                let mut state_windows = vec![];
-                if windows_len > 2 {
-                    if let Some((cache_module_path, cache_id, cache_index, cache_windows)) =
-                        self.switch_windows_state.cache.as_ref()
-                    {
-                        if cache_module_path == &module_path {
-                            if self.switch_windows_state.modifier_released {
-                                if *cache_id != current_id {
-                                    if let Some((i, _)) =
-                                        windows.iter().enumerate().find(|(_, (v, _))| v == cache_id)
-                                    {
-                                        index = i;
-                                    }
-                                }
-                            } else {
-                                state_id = *cache_id;
-                                let mut windows_set: IndexSet<isize> =
-                                    windows.iter().map(|(v, _)| v.0 as _).collect();
-                                for id in cache_windows {
-                                    if windows_set.contains(id) {
-                                        state_windows.push(*id);
-                                        windows_set.swap_remove(id);
-                                    }
-                                }
-                                state_windows.extend(windows_set);
-                                index = if reverse {
-                                    if *cache_index == 0 {
-                                        windows_len - 1
-                                    } else {
-                                        cache_index - 1
-                                    }
-                                } else if *cache_index >= windows_len - 1 {
-                                    0
-                                } else {
-                                    cache_index + 1
-                                };
-                            }
-                        }
-                    }
-                }
-                if state_windows.is_empty() {
-                    state_windows = windows.iter().map(|(v, _)| v.0 as _).collect();
-                }
-                let hwnd = HWND(state_windows[index] as _);
-                self.switch_windows_state = SwitchWindowsState {
-                    cache: Some((module_path.clone(), state_id, index, state_windows)),
-                    modifier_released: false,
-                };
-                set_foreground_window(hwnd);
-
-                Ok(true)
+    fn click(&mut self) {
+        if let Some(state) = self.switcher.switch_apps_state() {
+            match self.painter.find_clicked_item(state) {
+                Some(ClickedItem::App(i)) => self.switcher.select_app(i),
+                Some(ClickedItem::Window(i)) => self.switcher.select_window(i),
+                None => return,
             }
+            self.do_switch_app();
         }
     }
 
-    fn switch_apps(&mut self, reverse: bool) -> Result<()> {
-        debug!(
-            "switch apps: reverse:{reverse}, state:{:?}",
-            self.switch_apps_state
-        );
-        if let Some(state) = self.switch_apps_state.as_mut() {
-            if reverse {
-                if state.index == 0 {
-                    state.index = state.apps.len() - 1;
-                } else {
-                    state.index -= 1;
-                }
-            } else if state.index == state.apps.len() - 1 {
-                state.index = 0;
-            } else {
-                state.index += 1;
-            };
-            debug!("switch apps: new index:{}", state.index);
-            return Ok(());
-        }
-        let windows = list_windows(
-            self.config.switch_apps_ignore_minimal,
-            self.config.switch_apps_only_current_desktop(),
-            self.is_admin,
-        )?;
-        let mut apps = vec![];
-        for (module_path, hwnds) in windows.iter() {
-            let module_hwnd = if is_iconic_window(hwnds[0].0) {
-                hwnds[hwnds.len() - 1].0
-            } else {
-                hwnds[0].0
-            };
-            let module_hicon = self
-                .cached_icons
-                .entry(module_path.clone())
-                .or_insert_with(|| {
-                    get_app_icon(
-                        &self.config.switch_apps_override_icons,
-                        module_path,
-                        module_hwnd,
-                    )
-                });
-            apps.push((*module_hicon, module_hwnd));
-        }
-        let num_apps = apps.len() as i32;
-        if num_apps == 0 {
-            return Ok(());
-        }
-
-        let index = if apps.len() == 1 {
-            0
-        } else if reverse {
-            apps.len() - 1
-        } else {
-            1
-        };
-
-        let state = SwitchAppsState { apps, index };
-        self.switch_apps_state = Some(state);
-        debug!("switch apps, new state:{:?}", self.switch_apps_state);
-        Ok(())
-    }
-
-    fn click(&mut self) {
-        if let Some(state) = self.switch_apps_state.as_mut() {
-            if let Some(i) = self.painter.find_clicked_app_index(state) {
-                state.index = i;
-                self.do_switch_app();
-            }
+    /// Paints the app switcher and lets it know the layout for arrow-key navigation.
+    fn paint(&mut self) {
+        self.cancel_show_pending();
+        if let Some(state) = self.switcher.switch_apps_state() {
+            let columns = self.painter.paint(state);
+            self.switcher.set_columns(columns);
         }
     }
 
     fn do_switch_app(&mut self) {
-        if let Some(state) = self.switch_apps_state.take() {
-            if let Some((_, id)) = state.apps.get(state.index) {
-                set_foreground_window(*id);
-            }
+        self.cancel_show_pending();
+        self.keyboard_listener.set_switcher_pinned(false);
+        if let Some(state) = self.switcher.do_switch_app() {
             self.painter.unpaint(state);
         }
     }
 
     fn cancel_switch_app(&mut self) {
-        if let Some(state) = self.switch_apps_state.take() {
+        self.cancel_show_pending();
+        self.keyboard_listener.set_switcher_pinned(false);
+        if let Some(state) = self.switcher.cancel_switch_app() {
             self.painter.unpaint(state);
         }
     }
-}
 
-impl Drop for App {
-    fn drop(&mut self) {
-        for (_, icon) in self.cached_icons.drain() {
-            unsafe {
-                let _ = DestroyIcon(icon);
-            }
+    /// Stops waiting for the show delay, e.g. once the switcher is painted or closed.
+    fn cancel_show_pending(&mut self) {
+        if self.is_show_pending {
+            self.is_show_pending = false;
+            self.keyboard_listener.set_switcher_hidden(false);
+            let _ = unsafe { KillTimer(Some(self.hwnd), SHOW_SWITCHER_TIMER_ID) };
         }
     }
 }
@@ -487,15 +570,3 @@ fn get_app(hwnd: HWND) -> Result<&'static mut App> {
         Ok(tx)
     }
 }
-
-#[derive(Debug)]
-struct SwitchWindowsState {
-    cache: Option<(String, HWND, usize, Vec<isize>)>,
-    modifier_released: bool,
-}
-
-#[derive(Debug)]
-pub struct SwitchAppsState {
-    pub apps: Vec<(HICON, HWND)>,
-    pub index: usize,
-}
@@ -0,0 +1,19 @@
+//! The console version of `window-switcher <COMMAND>`.
+//!
+//! `window-switcher.exe` is a GUI app, which shells don't wait for, so its output shows up after
+//! the prompt and its exit code is lost. Shells wait for this one like for any console program.
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("Window-Switcher only runs on Windows");
+    std::process::exit(1);
+}
+
+#[cfg(windows)]
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(err) = window_switcher::run_cli(&args) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::ipc::Command;
+#[cfg(windows)]
+use crate::ipc_client::send_command;
+
+use anyhow::{bail, Result};
+use serde_json::Value;
+
+#[cfg_attr(not(windows), allow(dead_code))]
+const USAGE: &str = "\
+Usage: window-switcher-cli [COMMAND]
+       window-switcher [COMMAND]
+
+The commands are sent to the running instance. Shells and scripts wait for window-switcher-cli,
+without a command window-switcher starts Window-Switcher:
+  switch-apps [--reverse]     Switch to the next app
+  switch-windows [--reverse]  Switch to the next window of the current app
+  show-apps                   Show the app switcher, pick an app with Enter or a click
+  list [--json]               List the windows of all apps
+  reload                      Reload the config file
+  startup [enable|disable]    Show or change whether to start with Windows
+  quit                        Exit Window-Switcher
+  help                        Show this help";
+
+/// Runs the subcommand on the running instance and prints its response.
+#[cfg(windows)]
+pub fn run(args: &[String]) -> Result<()> {
+    let Some((command, json)) = parse_args(args)? else {
+        println!("{USAGE}");
+        return Ok(());
+    };
+    let data = send_command(command)?;
+    print!("{}", format_output(command, data, json));
+    Ok(())
+}
+
+/// Returns the command and whether to print JSON, or `None` for help.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_args(args: &[String]) -> Result<Option<(Command, bool)>> {
+    let Some((name, flags)) = args.split_first() else {
+        return Ok(None);
+    };
+    let flags: Vec<&str> = flags.iter().map(|v| v.as_str()).collect();
+    let reverse = flags == ["--reverse"];
+    let command = match (name.as_str(), flags.as_slice()) {
+        ("help" | "--help" | "-h", []) => return Ok(None),
+        ("switch-apps", [] | ["--reverse"]) => Command::SwitchApps { reverse },
+        ("switch-windows", [] | ["--reverse"]) => Command::SwitchWindows { reverse },
+        ("show-apps", []) => Command::ShowApps,
+        ("list", []) => Command::ListWindows,
+        ("list", ["--json"]) => return Ok(Some((Command::ListWindows, true))),
+        ("reload", []) => Command::ReloadConfig,
+        ("startup", []) => Command::Startup { enable: None },
+        ("startup", ["enable"]) => Command::Startup { enable: Some(true) },
+        ("startup", ["disable"]) => Command::Startup {
+            enable: Some(false),
+        },
+        ("quit", []) => Command::Quit,
+        _ => bail!("Invalid command '{}'\n\n{USAGE}", args.join(" ")),
+    };
+    Ok(Some((command, false)))
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn format_output(command: Command, data: Option<Value>, json: bool) -> String {
+    let Some(data) = data else {
+        return String::new();
+    };
+    if json {
+        return format!("{data}\n");
+    }
+    match command {
+        Command::ListWindows => {
+            let mut output = String::new();
+            for window in data.as_array().into_iter().flatten() {
+                let app = window["app"].as_str().unwrap_or_default();
+                let app = app.rsplit('\\').next().unwrap_or(app);
+                let title = window["title"].as_str().unwrap_or_default();
+                let hwnd = window["hwnd"].to_string();
+                output.push_str(&format!("{hwnd:<10} {app:<24} {title}\n"));
+            }
+            output
+        }
+        Command::Startup { .. } => match data["enabled"].as_bool() {
+            Some(true) => "enabled\n".to_string(),
+            _ => "disabled\n".to_string(),
+        },
+        _ => format!("{data}\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(args: &str) -> Result<Option<(Command, bool)>> {
+        let args: Vec<String> = args.split_whitespace().map(|v| v.to_string()).collect();
+        parse_args(&args)
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse("").unwrap(), None);
+        assert_eq!(parse("help").unwrap(), None);
+        assert_eq!(
+            parse("switch-apps --reverse").unwrap(),
+            Some((Command::SwitchApps { reverse: true }, false))
+        );
+        assert_eq!(
+            parse("list --json").unwrap(),
+            Some((Command::ListWindows, true))
+        );
+        assert_eq!(
+            parse("startup disable").unwrap(),
+            Some((
+                Command::Startup {
+                    enable: Some(false)
+                },
+                false
+            ))
+        );
+        assert_eq!(
+            parse("reload").unwrap(),
+            Some((Command::ReloadConfig, false))
+        );
+        assert!(parse("reload --json").is_err());
+        assert!(parse("startup maybe").is_err());
+        assert!(parse("foo").is_err());
+    }
+
+    #[test]
+    fn test_format_output() {
+        let windows = json!([
+            { "hwnd": 1234, "title": "a.rs", "app": r"C:\Code\Code.exe" },
+            { "hwnd": 42, "title": "Google", "app": r"C:\Chrome\chrome.exe::Profile 1" },
+        ]);
+        assert_eq!(
+            format_output(Command::ListWindows, Some(windows.clone()), false),
+            "1234       Code.exe                 a.rs\n\
+             42         chrome.exe::Profile 1    Google\n"
+        );
+        assert_eq!(
+            format_output(Command::ListWindows, Some(windows.clone()), true),
+            format!("{windows}\n")
+        );
+        assert_eq!(
+            format_output(
+                Command::Startup { enable: None },
+                Some(json!({ "enabled": true })),
+                false
+            ),
+            "enabled\n"
+        );
+        assert_eq!(format_output(Command::Quit, None, false), "");
+    }
+}
@@ -1,16 +1,25 @@
-use std::{collections::HashSet, fs, path::PathBuf, process::Command};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    path::PathBuf,
+    process::Command,
+};
 
 use anyhow::{anyhow, Result};
 use indexmap::IndexMap;
 use ini::{Ini, ParseOption};
 use log::LevelFilter;
-use windows::core::w;
 
-use crate::utils::{get_exe_folder, RegKey};
+use crate::rules::{GroupAction, GroupRule, Rule, RuleAction};
+use crate::utils::get_exe_folder;
 
 pub const SWITCH_WINDOWS_HOTKEY_ID: u32 = 1;
 pub const SWITCH_APPS_HOTKEY_ID: u32 = 2;
+pub const SWITCH_ALL_WINDOWS_HOTKEY_ID: u32 = 3;
+/// The hotkey of the launcher at index `i` has the id `LAUNCHER_HOTKEY_ID + i`.
+pub const LAUNCHER_HOTKEY_ID: u32 = 100;
 
+#[cfg_attr(not(windows), allow(dead_code))]
 const DEFAULT_CONFIG: &str = include_str!("../window-switcher.ini");
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,11 +31,38 @@ pub struct Config {
     pub switch_windows_blacklist: HashSet<String>,
     pub switch_windows_ignore_minimal: bool,
     switch_windows_only_current_desktop: Option<bool>,
+    pub switch_windows_only_current_monitor: bool,
+    /// Cycles every window in MRU order rather than the windows of the current app, off if empty.
+    pub switch_windows_all_hotkey: Vec<Hotkey>,
+    /// Whether to show the switcher with every window while cycling all windows.
+    pub switch_windows_all_overlay: bool,
     pub switch_apps_enable: bool,
     pub switch_apps_hotkey: Vec<Hotkey>,
     pub switch_apps_ignore_minimal: bool,
     pub switch_apps_override_icons: IndexMap<String, String>,
     switch_apps_only_current_desktop: Option<bool>,
+    pub switch_apps_only_current_monitor: bool,
+    pub switch_apps_window_list: bool,
+    pub switch_apps_position: Position,
+    /// The most icons in a row of the app switcher, 0 to fit as many as the monitor allows.
+    pub switch_apps_max_icons_per_row: usize,
+    /// Icons shrink to fit on a row down to this size, at 100% scale, then wrap into more rows.
+    pub switch_apps_min_icon_size: i32,
+    /// Milliseconds to wait before showing the app switcher, releasing the hotkey sooner
+    /// switches to the previous app without showing it.
+    pub switch_apps_show_delay: u32,
+    /// Keys acting on the selected entry while the app switcher is open.
+    pub switch_apps_action_keys: Vec<(u32, WindowAction)>,
+    /// Apps listed first in the app switcher, in this order.
+    pub switch_apps_pinned: Vec<Program>,
+    /// Whether pinned apps without windows are listed too, selecting one starts it.
+    pub switch_apps_pinned_closed: bool,
+    pub theme: Theme,
+    /// Includes or excludes windows from both switchers, in the order of the config file.
+    pub rules: Vec<Rule>,
+    /// Merges or splits the apps of windows, in the order of the config file.
+    pub groups: Vec<GroupRule>,
+    pub launchers: Vec<Launcher>,
 }
 
 impl Default for Config {
@@ -44,6 +80,9 @@ impl Default for Config {
             switch_windows_blacklist: Default::default(),
             switch_windows_ignore_minimal: false,
             switch_windows_only_current_desktop: None,
+            switch_windows_only_current_monitor: false,
+            switch_windows_all_hotkey: vec![],
+            switch_windows_all_overlay: false,
             switch_apps_enable: false,
             switch_apps_hotkey: vec![Hotkey::create(
                 SWITCH_APPS_HOTKEY_ID,
@@ -54,11 +93,26 @@ impl Default for Config {
             switch_apps_ignore_minimal: false,
             switch_apps_override_icons: Default::default(),
             switch_apps_only_current_desktop: None,
+            switch_apps_only_current_monitor: false,
+            switch_apps_window_list: false,
+            switch_apps_position: Position::Cursor,
+            switch_apps_max_icons_per_row: 0,
+            switch_apps_min_icon_size: 48,
+            switch_apps_show_delay: 0,
+            // delete
+            switch_apps_action_keys: vec![(0xe053, WindowAction::CloseApp)],
+            switch_apps_pinned: vec![],
+            switch_apps_pinned_closed: false,
+            theme: Theme::default(),
+            rules: vec![],
+            groups: vec![],
+            launchers: vec![],
         }
     }
 }
 
 impl Config {
+    /// Loads the config, invalid values keep their defaults, see [`validate_config`].
     pub fn load(ini_conf: &Ini) -> Result<Self> {
         let mut conf = Config::default();
         if let Some(section) = ini_conf.section(None::<String>) {
@@ -85,9 +139,8 @@ impl Config {
 
         if let Some(section) = ini_conf.section(Some("switch-windows")) {
             if let Some(v) = section.get("hotkey") {
-                if !v.trim().is_empty() {
-                    conf.switch_windows_hotkey =
-                        parse_hotkeys(SWITCH_WINDOWS_HOTKEY_ID, "switch windows", v)?;
+                if let Ok(v) = parse_hotkeys(SWITCH_WINDOWS_HOTKEY_ID, "switch windows", v) {
+                    conf.switch_windows_hotkey = v;
                 }
             }
 
@@ -107,15 +160,32 @@ impl Config {
             {
                 conf.switch_windows_only_current_desktop = Some(v);
             }
+            if let Some(v) = section
+                .get("only_current_monitor")
+                .and_then(Config::to_bool)
+            {
+                conf.switch_windows_only_current_monitor = v;
+            }
+            if let Some(v) = section.get("all_hotkey") {
+                if v.trim().is_empty() {
+                    conf.switch_windows_all_hotkey = vec![];
+                } else if let Ok(v) =
+                    parse_hotkeys(SWITCH_ALL_WINDOWS_HOTKEY_ID, "switch all windows", v)
+                {
+                    conf.switch_windows_all_hotkey = v;
+                }
+            }
+            if let Some(v) = section.get("all_overlay").and_then(Config::to_bool) {
+                conf.switch_windows_all_overlay = v;
+            }
         }
         if let Some(section) = ini_conf.section(Some("switch-apps")) {
             if let Some(v) = section.get("enable").and_then(Config::to_bool) {
                 conf.switch_apps_enable = v;
             }
             if let Some(v) = section.get("hotkey") {
-                if !v.trim().is_empty() {
-                    conf.switch_apps_hotkey =
-                        parse_hotkeys(SWITCH_APPS_HOTKEY_ID, "switch apps", v)?;
+                if let Ok(v) = parse_hotkeys(SWITCH_APPS_HOTKEY_ID, "switch apps", v) {
+                    conf.switch_apps_hotkey = v;
                 }
             }
             if let Some(v) = section.get("ignore_minimal").and_then(Config::to_bool) {
@@ -138,12 +208,113 @@ impl Config {
             {
                 conf.switch_apps_only_current_desktop = Some(v);
             }
+            if let Some(v) = section
+                .get("only_current_monitor")
+                .and_then(Config::to_bool)
+            {
+                conf.switch_apps_only_current_monitor = v;
+            }
+            if let Some(v) = section.get("window_list").and_then(Config::to_bool) {
+                conf.switch_apps_window_list = v;
+            }
+            if let Some(v) = section.get("position").and_then(Position::parse) {
+                conf.switch_apps_position = v;
+            }
+            if let Some(v) = section.get("max_icons_per_row").and_then(parse_size) {
+                conf.switch_apps_max_icons_per_row = v as usize;
+            }
+            if let Some(v) = section.get("min_icon_size").and_then(parse_size) {
+                conf.switch_apps_min_icon_size = v as i32;
+            }
+            if let Some(v) = section.get("show_delay").and_then(parse_size) {
+                conf.switch_apps_show_delay = v;
+            }
+            for action in WindowAction::ALL {
+                if let Some(codes) = section.get(action.name()).and_then(parse_keys) {
+                    conf.switch_apps_action_keys.retain(|(_, v)| *v != action);
+                    conf.switch_apps_action_keys
+                        .extend(codes.into_iter().map(|code| (code, action)));
+                }
+            }
+            if let Some(v) = section.get("pinned") {
+                conf.switch_apps_pinned = v.split(',').filter_map(Program::parse).collect();
+            }
+            if let Some(v) = section.get("pinned_closed").and_then(Config::to_bool) {
+                conf.switch_apps_pinned_closed = v;
+            }
+        }
+        if let Some(section) = ini_conf.section(Some("theme")) {
+            let theme = &mut conf.theme;
+            for (prefix, colors) in [("light", &mut theme.light), ("dark", &mut theme.dark)] {
+                for (name, color) in [
+                    ("background", &mut colors.background),
+                    ("selection", &mut colors.selection),
+                    ("border", &mut colors.border),
+                ] {
+                    if let Some(v) = section
+                        .get(format!("{prefix}_{name}"))
+                        .and_then(parse_color)
+                    {
+                        *color = v;
+                    }
+                }
+            }
+            if let Some(v) = section.get("opacity").and_then(parse_percent) {
+                theme.opacity = v;
+            }
+            if let Some(v) = section.get("corner_radius") {
+                if v.trim() == "auto" {
+                    theme.corner_radius = None;
+                } else if let Some(v) = parse_size(v) {
+                    theme.corner_radius = Some(v as i32);
+                }
+            }
+            if let Some(v) = section.get("icon_size").and_then(parse_size) {
+                theme.icon_size = v as i32;
+            }
+        }
+        if let Some(section) = ini_conf.section(Some("rules")) {
+            for (key, value) in section.iter() {
+                let Some(action) = RuleAction::ALL.into_iter().find(|v| v.name() == key) else {
+                    continue;
+                };
+                if let Ok(rule) = Rule::parse(action, value) {
+                    conf.rules.push(rule);
+                }
+            }
+        }
+        if let Some(section) = ini_conf.section(Some("groups")) {
+            let by_aumid = section.get("by_aumid").and_then(Config::to_bool);
+            for (key, value) in section.iter() {
+                let Some(action) = GroupAction::ALL.into_iter().find(|v| v.name() == key) else {
+                    continue;
+                };
+                if let Ok(group) = GroupRule::parse(action, value) {
+                    conf.groups.push(group);
+                }
+            }
+            // after the user rules, so they still decide for the windows they match
+            if by_aumid == Some(true) {
+                conf.groups
+                    .push(GroupRule::parse(GroupAction::Aumid, "exe=*").unwrap());
+            }
+        }
+        if let Some(section) = ini_conf.section(Some("launchers")) {
+            for (key, value) in section.iter() {
+                let id = LAUNCHER_HOTKEY_ID + conf.launchers.len() as u32;
+                let (key, value) = launcher_entry(key, value);
+                if let Ok(v) = Launcher::parse(id, &key, &value) {
+                    conf.launchers.push(v);
+                }
+            }
         }
         Ok(conf)
     }
 
     pub fn to_hotkeys(&self) -> Vec<&Hotkey> {
         let mut hotkeys: Vec<&Hotkey> = self.switch_windows_hotkey.iter().collect();
+        hotkeys.extend(self.switch_windows_all_hotkey.iter());
+        hotkeys.extend(self.launchers.iter().map(|v| &v.hotkey));
         if self.switch_apps_enable {
             hotkeys.extend(self.switch_apps_hotkey.iter());
         }
@@ -174,7 +345,11 @@ impl Config {
             .unwrap_or_else(Self::system_switcher_only_current_desktop)
     }
 
+    #[cfg(windows)]
     fn system_switcher_only_current_desktop() -> bool {
+        use crate::utils::RegKey;
+        use windows::core::w;
+
         let alt_tab_filter = RegKey::new_hkcu(
             w!(r"Software\Microsoft\Windows\CurrentVersion\Explorer\Advanced"),
             w!("VirtualDesktopAltTabFilter"),
@@ -184,51 +359,287 @@ impl Config {
 
         alt_tab_filter != 0
     }
+
+    /// Windows' default, only the windows of the current desktop.
+    #[cfg(not(windows))]
+    fn system_switcher_only_current_desktop() -> bool {
+        true
+    }
+}
+
+/// Where the app switcher shows up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+    /// Centered on the primary monitor.
+    Primary,
+    /// Centered on the monitor with the cursor.
+    Cursor,
+    /// Centered on the monitor with the foreground window.
+    Foreground,
+    /// The top-left corner at these screen coordinates, in physical pixels.
+    Fixed(i32, i32),
+}
+
+impl Position {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value {
+            "primary" => Some(Position::Primary),
+            "cursor" => Some(Position::Cursor),
+            "foreground" => Some(Position::Foreground),
+            _ => {
+                let (x, y) = value.split_once(',')?;
+                Some(Position::Fixed(
+                    x.trim().parse().ok()?,
+                    y.trim().parse().ok()?,
+                ))
+            }
+        }
+    }
+}
+
+/// The look of the app switcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub light: ThemeColors,
+    pub dark: ThemeColors,
+    /// The opacity of the whole switcher in percent.
+    pub opacity: u8,
+    /// In pixels at 100% scale, `None` rounds the corners on Windows 11 only.
+    pub corner_radius: Option<i32>,
+    /// The largest icon size in pixels at 100% scale.
+    pub icon_size: i32,
 }
 
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            light: ThemeColors {
+                background: 0xffe0e0e0,
+                selection: 0xfff2f2f2,
+                border: 0,
+            },
+            dark: ThemeColors {
+                background: 0xff4c4c4c,
+                selection: 0xff3b3b3b,
+                border: 0,
+            },
+            opacity: 100,
+            corner_radius: None,
+            icon_size: 64,
+        }
+    }
+}
+
+impl Theme {
+    pub fn colors(&self, light_theme: bool) -> &ThemeColors {
+        if light_theme {
+            &self.light
+        } else {
+            &self.dark
+        }
+    }
+}
+
+/// Colors as `0xAARRGGBB`, the way GDI+ takes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThemeColors {
+    pub background: u32,
+    /// The box behind the selected icon or window.
+    pub selection: u32,
+    /// The outline of the switcher, transparent means no outline.
+    pub border: u32,
+}
+
+/// What can be done to the selected entry of the app switcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAction {
+    /// Closes all windows of the app.
+    CloseApp,
+    CloseWindow,
+    Minimize,
+    Restore,
+    /// Terminates the process.
+    Kill,
+}
+
+impl WindowAction {
+    /// Indexed by the discriminant, which is how actions are sent in messages.
+    pub const ALL: [WindowAction; 5] = [
+        WindowAction::CloseApp,
+        WindowAction::CloseWindow,
+        WindowAction::Minimize,
+        WindowAction::Restore,
+        WindowAction::Kill,
+    ];
+
+    /// The config key of the action.
+    pub fn name(self) -> &'static str {
+        match self {
+            WindowAction::CloseApp => "close_app",
+            WindowAction::CloseWindow => "close_window",
+            WindowAction::Minimize => "minimize",
+            WindowAction::Restore => "restore",
+            WindowAction::Kill => "kill",
+        }
+    }
+}
+
+/// A program to focus or start, by the path or file name of its executable,
+/// or by the AppUserModelID of a packaged app.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub target: String,
+}
+
+impl Program {
+    pub fn parse(value: &str) -> Option<Self> {
+        let target = normalize_path_value(value.trim().trim_matches('"'));
+        (!target.is_empty()).then_some(Self { target })
+    }
+
+    /// Executables are told from AppUserModelIDs by their `.exe` extension or their folder.
+    pub fn is_aumid(&self) -> bool {
+        !self.target.contains('\\') && !self.target.to_ascii_lowercase().ends_with(".exe")
+    }
+
+    /// Whether windows of this executable belong to the program.
+    pub fn matches_module_path(&self, module_path: &str) -> bool {
+        if self.target.contains('\\') {
+            module_path.eq_ignore_ascii_case(&self.target)
+        } else {
+            let name = module_path.rsplit('\\').next().unwrap_or(module_path);
+            name.eq_ignore_ascii_case(&self.target)
+        }
+    }
+
+    /// What the shell opens to start the program.
+    pub fn shell_path(&self) -> String {
+        if self.is_aumid() {
+            format!(r"shell:AppsFolder\{}", self.target)
+        } else {
+            self.target.clone()
+        }
+    }
+}
+
+/// Focuses the windows of a program with a hotkey, or starts it if none are open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Launcher {
+    pub hotkey: Hotkey,
+    pub program: Program,
+}
+
+impl Launcher {
+    pub fn parse(id: u32, hotkey: &str, program: &str) -> Result<Self> {
+        let hotkey = Hotkey::create(id, "launcher", hotkey)?;
+        let program = Program::parse(program).ok_or_else(|| anyhow!("Missing launcher program"))?;
+        Ok(Self { hotkey, program })
+    }
+}
+
+const SCANCODE_PRINTSCREEN: u32 = 0xe037;
+const SCANCODE_SYSRQ: u32 = 0x54;
+const SCANCODE_ALTS: [u32; 2] = [0x38, 0xe038];
+
+/// Scan codes of all modifier keys, extended keys are prefixed with `0xe0`.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const MODIFIER_KEYS: [u32; 8] = [0x1d, 0xe01d, 0x38, 0xe038, 0x2a, 0x36, 0xe05b, 0xe05c];
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Hotkey {
     pub id: u32,
     pub name: String,
-    pub modifier: [u32; 2],
+    /// Every modifier must be held; each one is satisfied by either of its two scan codes.
+    pub modifiers: Vec<[u32; 2]>,
     pub code: u32,
 }
 
 impl Hotkey {
     pub fn create(id: u32, name: &str, value: &str) -> Result<Self> {
-        let (modifier, code) =
+        let (modifiers, code) =
             Self::parse(value).ok_or_else(|| anyhow!("Invalid {name} hotkey"))?;
         Ok(Self {
             id,
             name: name.to_string(),
-            modifier,
+            modifiers,
             code,
         })
     }
 
-    pub fn get_modifier(&self) -> u32 {
-        self.modifier[0]
+    pub fn has_modifier(&self, scan_code: u32) -> bool {
+        self.modifiers.iter().any(|v| v.contains(&scan_code))
+    }
+
+    /// Whether the key is the key of the hotkey.
+    ///
+    /// While Alt is held, PrintScreen is reported as SysRq, so it matches too.
+    pub fn matches(&self, scan_code: u32) -> bool {
+        scan_code == self.code
+            || (self.code == SCANCODE_PRINTSCREEN
+                && scan_code == SCANCODE_SYSRQ
+                && SCANCODE_ALTS.iter().any(|v| self.has_modifier(*v)))
     }
 
-    pub fn parse(value: &str) -> Option<([u32; 2], u32)> {
+    pub fn parse(value: &str) -> Option<(Vec<[u32; 2]>, u32)> {
         let value = value
             .to_ascii_lowercase()
             .replace(' ', "")
             .replace("vk_", "");
         let keys: Vec<&str> = value.split('+').collect();
-        if keys.len() != 2 {
+        let (key, modifier_keys) = keys.split_last()?;
+        if modifier_keys.is_empty() {
             return None;
         }
-        let modifier = match keys[0] {
-            "win" => [0x5b, 0x5c],
-            "alt" => [0x38, 0x38],
-            "ctrl" => [0x1d, 0x1d],
-            _ => {
+        let mut modifiers: Vec<[u32; 2]> = vec![];
+        for name in modifier_keys {
+            let modifier = Self::parse_modifier(name)?;
+            if modifiers
+                .iter()
+                .any(|v| v.iter().any(|c| modifier.contains(c)))
+            {
                 return None;
             }
+            modifiers.push(modifier);
+        }
+        let code = Self::parse_key(key)?;
+        Some((modifiers, code))
+    }
+
+    fn parse_modifier(name: &str) -> Option<[u32; 2]> {
+        let modifier = match name {
+            "win" => [0xe05b, 0xe05c],
+            "lwin" => [0xe05b, 0xe05b],
+            "rwin" => [0xe05c, 0xe05c],
+            "alt" => [0x38, 0xe038],
+            "lalt" => [0x38, 0x38],
+            "ralt" => [0xe038, 0xe038],
+            "ctrl" => [0x1d, 0xe01d],
+            "lctrl" => [0x1d, 0x1d],
+            "rctrl" => [0xe01d, 0xe01d],
+            "shift" => [0x2a, 0x36],
+            "lshift" => [0x2a, 0x2a],
+            "rshift" => [0x36, 0x36],
+            _ => return None,
         };
+        Some(modifier)
+    }
+
+    /// Parses a key name into its scan code, extended keys are prefixed with `0xe0`.
+    ///
+    /// Besides key names, `sc:<code>` takes a raw scan code and `vk:<code>` a virtual-key code,
+    /// e.g. `sc:0xe01d` or `vk:0x7c`.
+    fn parse_key(name: &str) -> Option<u32> {
+        if let Some(value) = name.strip_prefix("sc:") {
+            // the hook only reports plain scan codes and extended ones with the 0xe0 prefix
+            return parse_number(value).filter(|v| matches!(v, 0x01..=0xff | 0xe001..=0xe0ff));
+        }
+        if let Some(value) = name.strip_prefix("vk:") {
+            let vk = parse_number(value).filter(|v| *v != 0 && *v <= 0xff)?;
+            return vk_to_scan_code(vk);
+        }
         // see <https://kbdlayout.info/kbdus/overview+scancodes>
-        let code = match keys[1] {
+        let code = match name {
             "esc" | "escape" => 0x01,
             "1" | "!" => 0x02,
             "2" | "@" => 0x03,
@@ -292,41 +703,376 @@ impl Hotkey {
             "f8" => 0x42,
             "f9" => 0x43,
             "f10" => 0x44,
-            "numlock" => 0x45,
+            "numlock" => 0xe045,
             "scrolllock" => 0x46,
-            "home" => 0x47,
-            "up" => 0x48,
-            "pageup" => 0x49,
-            "left" => 0x4b,
-            "right" => 0x4d,
-            "end" => 0x4f,
-            "down" => 0x50,
-            "pagedown" => 0x51,
-            "insert" => 0x52,
-            "delete" => 0x53,
-            "prtsc" | "printscreen" => 0x54,
+            "home" => 0xe047,
+            "up" => 0xe048,
+            "pageup" => 0xe049,
+            "left" => 0xe04b,
+            "right" => 0xe04d,
+            "end" => 0xe04f,
+            "down" => 0xe050,
+            "pagedown" => 0xe051,
+            "insert" => 0xe052,
+            "delete" => 0xe053,
+            "prtsc" | "printscreen" => SCANCODE_PRINTSCREEN,
             "oem_102" => 0x56,
             "f11" => 0x57,
             "f12" => 0x58,
-            "menu" => 0x5d,
+            "f13" => 0x64,
+            "f14" => 0x65,
+            "f15" => 0x66,
+            "f16" => 0x67,
+            "f17" => 0x68,
+            "f18" => 0x69,
+            "f19" => 0x6a,
+            "f20" => 0x6b,
+            "f21" => 0x6c,
+            "f22" => 0x6d,
+            "f23" => 0x6e,
+            "f24" => 0x76,
+            "menu" | "apps" => 0xe05d,
+            "pause" => 0x45,
+            "num0" | "numpad0" => 0x52,
+            "num1" | "numpad1" => 0x4f,
+            "num2" | "numpad2" => 0x50,
+            "num3" | "numpad3" => 0x51,
+            "num4" | "numpad4" => 0x4b,
+            "num5" | "numpad5" => 0x4c,
+            "num6" | "numpad6" => 0x4d,
+            "num7" | "numpad7" => 0x47,
+            "num8" | "numpad8" => 0x48,
+            "num9" | "numpad9" => 0x49,
+            "numdecimal" | "decimal" => 0x53,
+            "numadd" | "add" => 0x4e,
+            "numsubtract" | "subtract" => 0x4a,
+            "nummultiply" | "multiply" => 0x37,
+            "numdivide" | "divide" => 0xe035,
+            "numenter" => 0xe01c,
+            "media_next_track" | "media_next" => 0xe019,
+            "media_prev_track" | "media_prev" => 0xe010,
+            "media_stop" => 0xe024,
+            "media_play_pause" => 0xe022,
+            "volume_mute" => 0xe020,
+            "volume_down" => 0xe02e,
+            "volume_up" => 0xe030,
+            "browser_back" => 0xe06a,
+            "browser_forward" => 0xe069,
+            "browser_refresh" => 0xe067,
+            "browser_stop" => 0xe068,
+            "browser_search" => 0xe065,
+            "browser_favorites" => 0xe066,
+            "browser_home" => 0xe032,
+            "launch_mail" => 0xe06c,
+            "launch_media_select" => 0xe06d,
+            "launch_app1" => 0xe06b,
+            "launch_app2" => 0xe021,
             _ => return None,
         };
-        Some((modifier, code))
+        Some(code)
     }
 }
 
-pub fn load_config() -> Result<Config> {
+/// Loads the config file, along with the problems found in it.
+pub fn load_config() -> Result<(Config, Vec<ConfigIssue>)> {
     let filepath = get_config_path()?;
+    if !filepath.exists() {
+        return Ok((Config::default(), vec![]));
+    }
+    let text = fs::read_to_string(&filepath)
+        .map_err(|err| anyhow!("Failed to read config file '{}', {err}", filepath.display()))?;
+    let text = text.trim_start_matches('\u{feff}');
     let opt = ParseOption {
         enabled_escape: false,
         ..Default::default()
     };
-    let conf = Ini::load_from_file_opt(&filepath, opt)
+    let conf = Ini::load_from_str_opt(text, opt)
         .map_err(|err| anyhow!("Failed to load config file '{}', {err}", filepath.display()))?;
-    Config::load(&conf)
+    let issues = validate_config(&conf, text);
+    let config = Config::load(&conf)?;
+    Ok((config, issues))
+}
+
+/// A problem in the config file, such as an unknown key or an invalid value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub line: Option<usize>,
+    pub section: Option<String>,
+    pub key: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        if let Some(section) = &self.section {
+            write!(f, "[{section}] ")?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, "{key}: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Logs the issues and joins them into a message for `alert!`.
+pub fn format_config_issues(issues: &[ConfigIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| {
+            warn!("config {issue}");
+            issue.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-pub(crate) fn edit_config_file() -> Result<bool> {
+#[derive(Debug, Clone, Copy)]
+enum ValueKind {
+    Bool,
+    BoolOrAuto,
+    LogLevel,
+    Hotkeys,
+    Keys,
+    Size,
+    SizeOrAuto,
+    Position,
+    Color,
+    Percent,
+    OverrideIcons,
+    Rule,
+    Text,
+}
+
+type SectionSchema = (Option<&'static str>, &'static [(&'static str, ValueKind)]);
+
+/// All known sections and keys, `None` is the general section.
+const CONFIG_SCHEMA: &[SectionSchema] = &[
+    (None, &[("trayicon", ValueKind::Bool)]),
+    (
+        Some("switch-windows"),
+        &[
+            ("hotkey", ValueKind::Hotkeys),
+            ("blacklist", ValueKind::Text),
+            ("ignore_minimal", ValueKind::Bool),
+            ("only_current_desktop", ValueKind::BoolOrAuto),
+            ("only_current_monitor", ValueKind::Bool),
+            ("all_hotkey", ValueKind::Hotkeys),
+            ("all_overlay", ValueKind::Bool),
+        ],
+    ),
+    (
+        Some("switch-apps"),
+        &[
+            ("enable", ValueKind::Bool),
+            ("hotkey", ValueKind::Hotkeys),
+            ("ignore_minimal", ValueKind::Bool),
+            ("override_icons", ValueKind::OverrideIcons),
+            ("only_current_desktop", ValueKind::BoolOrAuto),
+            ("only_current_monitor", ValueKind::Bool),
+            ("window_list", ValueKind::Bool),
+            ("position", ValueKind::Position),
+            ("max_icons_per_row", ValueKind::Size),
+            ("min_icon_size", ValueKind::Size),
+            ("show_delay", ValueKind::Size),
+            ("close_app", ValueKind::Keys),
+            ("close_window", ValueKind::Keys),
+            ("minimize", ValueKind::Keys),
+            ("restore", ValueKind::Keys),
+            ("kill", ValueKind::Keys),
+            ("pinned", ValueKind::Text),
+            ("pinned_closed", ValueKind::Bool),
+        ],
+    ),
+    // keys are hotkeys, see `check_launcher`
+    (Some("launchers"), &[]),
+    (
+        Some("theme"),
+        &[
+            ("light_background", ValueKind::Color),
+            ("light_selection", ValueKind::Color),
+            ("light_border", ValueKind::Color),
+            ("dark_background", ValueKind::Color),
+            ("dark_selection", ValueKind::Color),
+            ("dark_border", ValueKind::Color),
+            ("opacity", ValueKind::Percent),
+            ("corner_radius", ValueKind::SizeOrAuto),
+            ("icon_size", ValueKind::Size),
+        ],
+    ),
+    (
+        Some("rules"),
+        &[("include", ValueKind::Rule), ("exclude", ValueKind::Rule)],
+    ),
+    (
+        Some("groups"),
+        &[
+            ("by_aumid", ValueKind::Bool),
+            ("merge", ValueKind::Rule),
+            ("split", ValueKind::Rule),
+            ("aumid", ValueKind::Rule),
+        ],
+    ),
+    (
+        Some("log"),
+        &[("level", ValueKind::LogLevel), ("path", ValueKind::Text)],
+    ),
+];
+
+impl ValueKind {
+    /// Returns the reason why the value is invalid, empty values mean the default.
+    fn check(self, value: &str) -> Option<String> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        match self {
+            ValueKind::Bool if Config::to_bool(value).is_none() => Some(format!(
+                "invalid boolean '{value}', expected yes/no/true/false/on/off/1/0"
+            )),
+            ValueKind::BoolOrAuto if value != "auto" && Config::to_bool(value).is_none() => Some(
+                format!("invalid boolean '{value}', expected auto/yes/no/true/false/on/off/1/0"),
+            ),
+            ValueKind::LogLevel if value.parse::<LevelFilter>().is_err() => Some(format!(
+                "invalid log level '{value}', expected off/error/warn/info/debug/trace"
+            )),
+            ValueKind::Hotkeys => value
+                .split("||")
+                .map(str::trim)
+                .find(|v| !v.is_empty() && Hotkey::parse(v).is_none())
+                .map(|v| format!("invalid hotkey '{v}'")),
+            ValueKind::Size if parse_size(value).is_none() => Some(format!(
+                "invalid number '{value}', expected a number between 0 and 4096"
+            )),
+            ValueKind::SizeOrAuto if value != "auto" && parse_size(value).is_none() => Some(
+                format!("invalid number '{value}', expected auto or a number between 0 and 4096"),
+            ),
+            ValueKind::Position if Position::parse(value).is_none() => Some(format!(
+                "invalid position '{value}', expected cursor/foreground/primary or x,y"
+            )),
+            ValueKind::Color if parse_color(value).is_none() => Some(format!(
+                "invalid color '{value}', expected #RRGGBB or #AARRGGBB"
+            )),
+            ValueKind::Percent if parse_percent(value).is_none() => Some(format!(
+                "invalid percentage '{value}', expected a number between 1 and 100"
+            )),
+            ValueKind::Keys => value
+                .split("||")
+                .map(str::trim)
+                .find(|v| !v.is_empty() && parse_keys(v).is_none())
+                .map(|v| format!("invalid key '{v}'")),
+            ValueKind::OverrideIcons => normalize_path_value(value)
+                .split([',', ';'])
+                .map(str::trim)
+                .find(|v| match v.split_once('=') {
+                    Some((app, icon)) => app.trim().is_empty() || icon.trim().is_empty(),
+                    None => !v.is_empty(),
+                })
+                .map(|v| format!("malformed entry '{v}', expected app.exe=icon.ico")),
+            ValueKind::Rule => Rule::parse(RuleAction::Include, value).err(),
+            _ => None,
+        }
+    }
+}
+
+/// Checks the parsed config against the known sections and keys.
+/// `text` is the source of `ini_conf`, it is used to find the line numbers.
+pub fn validate_config(ini_conf: &Ini, text: &str) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    let mut seen: HashMap<(Option<&str>, String), usize> = HashMap::new();
+    for (section, props) in ini_conf.iter() {
+        let Some((_, keys)) = CONFIG_SCHEMA.iter().find(|(name, _)| *name == section) else {
+            issues.push(ConfigIssue {
+                line: find_line(text, section, None, 0),
+                section: section.map(|v| v.to_string()),
+                key: None,
+                message: "unknown section".into(),
+            });
+            continue;
+        };
+        for (key, value) in props.iter() {
+            let (key, value) = match section {
+                Some("launchers") => launcher_entry(key, value),
+                _ => (key.to_string(), value.to_string()),
+            };
+            let nth = seen.entry((section, key.clone())).or_default();
+            let message = match keys.iter().find(|(name, _)| *name == key) {
+                Some((_, kind)) => kind.check(&value),
+                None if section == Some("launchers") => check_launcher(&key, &value),
+                None => Some("unknown key".into()),
+            };
+            if let Some(message) = message {
+                issues.push(ConfigIssue {
+                    line: find_line(text, section, Some(&key), *nth),
+                    section: section.map(|v| v.to_string()),
+                    key: Some(key),
+                    message,
+                });
+            }
+            *nth += 1;
+        }
+    }
+    issues
+}
+
+/// The ini parser also ends keys at `:`, so `win+sc:0x3a = app.exe` is read as the key
+/// `win+sc` with the value `0x3a = app.exe`. Puts such hotkeys back together.
+fn launcher_entry(key: &str, value: &str) -> (String, String) {
+    let last = key.rsplit('+').next().unwrap_or_default().trim();
+    if last.eq_ignore_ascii_case("sc") || last.eq_ignore_ascii_case("vk") {
+        if let Some((code, program)) = value.split_once('=') {
+            return (format!("{key}:{}", code.trim()), program.trim().to_string());
+        }
+    }
+    (key.to_string(), value.to_string())
+}
+
+fn check_launcher(hotkey: &str, target: &str) -> Option<String> {
+    if Hotkey::parse(hotkey).is_none() {
+        Some(format!("invalid hotkey '{hotkey}'"))
+    } else if target.trim().trim_matches('"').is_empty() {
+        Some("missing program".into())
+    } else {
+        None
+    }
+}
+
+/// Finds the 1-based line of the `nth` occurrence of a key, or of the section header if `key` is `None`.
+fn find_line(text: &str, section: Option<&str>, key: Option<&str>, nth: usize) -> Option<usize> {
+    let mut current = None;
+    let mut count = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            current = Some(name.trim());
+            if key.is_none() && current == section {
+                return Some(i + 1);
+            }
+            continue;
+        }
+        let Some(key) = key else {
+            continue;
+        };
+        // keys may contain `:`, such as `win+sc:0x3a`, so only `=` ends them
+        let name = line.split('=').next().unwrap_or_default().trim();
+        if current == section && name == key {
+            if count == nth {
+                return Some(i + 1);
+            }
+            count += 1;
+        }
+    }
+    None
+}
+
+/// Opens the config file in notepad, changes are picked up by the config watcher.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub(crate) fn edit_config_file() -> Result<()> {
     let filepath = get_config_path()?;
     debug!("open config file '{}'", filepath.display());
     if !filepath.exists() {
@@ -337,31 +1083,89 @@ pub(crate) fn edit_config_file() -> Result<bool> {
             )
         })?;
     }
-    let exit = Command::new("notepad.exe")
+    Command::new("notepad.exe")
         .arg(&filepath)
         .spawn()
-        .map_err(|err| anyhow!("Failed to open config file '{}', {err}", filepath.display()))?
-        .wait()
-        .map_err(|err| {
-            anyhow!(
-                "Failed to close config file '{}', {err}",
-                filepath.display()
-            )
-        })?;
-
-    Ok(exit.success())
+        .map_err(|err| anyhow!("Failed to open config file '{}', {err}", filepath.display()))?;
+    Ok(())
 }
 
-fn get_config_path() -> Result<PathBuf> {
+pub(crate) fn get_config_path() -> Result<PathBuf> {
     let folder = get_exe_folder()?;
     let config_path = folder.join("window-switcher.ini");
     Ok(config_path)
 }
 
+fn parse_number(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Maps a virtual-key code to its scan code in the current keyboard layout.
+#[cfg(windows)]
+fn vk_to_scan_code(vk: u32) -> Option<u32> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{MapVirtualKeyW, MAPVK_VK_TO_VSC_EX};
+
+    let code = unsafe { MapVirtualKeyW(vk, MAPVK_VK_TO_VSC_EX) };
+    if code == 0 {
+        None
+    } else {
+        Some(code)
+    }
+}
+
+/// There are no keyboard layouts to map virtual-key codes with outside of Windows.
+#[cfg(not(windows))]
+fn vk_to_scan_code(_vk: u32) -> Option<u32> {
+    None
+}
+
 fn normalize_path_value(value: &str) -> String {
     value.replace("\\\\", "\\")
 }
 
+/// Parses a size such as a count or a length in pixels.
+fn parse_size(value: &str) -> Option<u32> {
+    value.trim().parse().ok().filter(|v| *v <= 4096)
+}
+
+/// Parses `#RRGGBB` or `#AARRGGBB` into `0xAARRGGBB`, colors without alpha are opaque.
+fn parse_color(value: &str) -> Option<u32> {
+    let hex = value.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let color = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        6 => Some(0xff000000 | color),
+        8 => Some(color),
+        _ => None,
+    }
+}
+
+/// Parses a percentage such as `80` or `80%`, zero is not allowed.
+fn parse_percent(value: &str) -> Option<u8> {
+    value
+        .trim()
+        .trim_end_matches('%')
+        .trim_end()
+        .parse()
+        .ok()
+        .filter(|v| (1..=100).contains(v))
+}
+
+/// Parses keys without modifiers separated by `||`, an empty value means no key.
+fn parse_keys(value: &str) -> Option<Vec<u32>> {
+    value
+        .split("||")
+        .map(|v| v.to_ascii_lowercase().replace(' ', "").replace("vk_", ""))
+        .filter(|v| !v.is_empty())
+        .map(|v| Hotkey::parse_key(&v))
+        .collect()
+}
+
 fn parse_hotkeys(id: u32, name: &str, value: &str) -> Result<Vec<Hotkey>> {
     let parts: Vec<&str> = value.split("||").collect();
     let mut hotkeys = vec![];
@@ -384,22 +1188,378 @@ mod tests {
 
     #[test]
     fn test_hotkey() {
-        assert_eq!(Hotkey::parse("alt + `"), Some(([0x38, 0x38], 0x29)));
-        assert_eq!(Hotkey::parse("alt + tab"), Some(([0x38, 0x38], 0x0f)));
+        assert_eq!(Hotkey::parse("alt + `"), Some((vec![[0x38, 0xe038]], 0x29)));
+        assert_eq!(
+            Hotkey::parse("alt + tab"),
+            Some((vec![[0x38, 0xe038]], 0x0f))
+        );
+    }
+
+    #[test]
+    fn test_hotkey_modifiers() {
+        assert_eq!(
+            Hotkey::parse("ctrl + alt + tab"),
+            Some((vec![[0x1d, 0xe01d], [0x38, 0xe038]], 0x0f))
+        );
+        assert_eq!(
+            Hotkey::parse("shift + win + q"),
+            Some((vec![[0x2a, 0x36], [0xe05b, 0xe05c]], 0x10))
+        );
+        assert_eq!(
+            Hotkey::parse("rctrl + space"),
+            Some((vec![[0xe01d, 0xe01d]], 0x39))
+        );
+        assert_eq!(Hotkey::parse("tab"), None);
+        assert_eq!(Hotkey::parse("alt + foo + tab"), None);
+        assert_eq!(Hotkey::parse("alt + lalt + tab"), None);
+    }
+
+    #[test]
+    fn test_hotkey_keys() {
+        let code = |value: &str| Hotkey::parse(value).map(|(_, code)| code);
+        assert_eq!(code("alt+f13"), Some(0x64));
+        assert_eq!(code("alt+f24"), Some(0x76));
+        assert_eq!(code("ctrl+up"), Some(0xe048));
+        assert_eq!(code("ctrl+num8"), Some(0x48));
+        assert_eq!(code("ctrl+enter"), Some(0x1c));
+        assert_eq!(code("ctrl+numenter"), Some(0xe01c));
+        assert_eq!(code("win+media_play_pause"), Some(0xe022));
+        assert_eq!(code("win+browser_back"), Some(0xe06a));
+        assert_eq!(code("alt+sc:0x29"), Some(0x29));
+        assert_eq!(code("alt+sc:0xe05d"), Some(0xe05d));
+        assert_eq!(code("alt+sc:41"), Some(0x29));
+        assert_eq!(code("alt+sc:0"), None);
+        assert_eq!(code("alt+sc:0x100"), None);
+        assert_eq!(code("alt+sc:0xdfff"), None);
+        assert_eq!(code("alt+sc:0xe000"), None);
+        assert_eq!(code("alt+sc:0xe100"), None);
+        assert_eq!(code("alt+sc:xyz"), None);
+        #[cfg(windows)]
+        assert_eq!(code("alt+vk:0x7c"), Some(0x64));
+        assert_eq!(code("alt+vk:0x100"), None);
+        assert_eq!(code("alt+f25"), None);
+    }
+
+    #[test]
+    fn test_hotkey_matches() {
+        let hotkey = |value: &str| Hotkey::create(1, "test", value).unwrap();
+        assert!(hotkey("alt+`").matches(0x29));
+        assert!(!hotkey("alt+`").matches(0x54));
+        // PrintScreen is SysRq while Alt is held
+        assert!(hotkey("alt+printscreen").matches(0xe037));
+        assert!(hotkey("alt+printscreen").matches(0x54));
+        assert!(hotkey("ralt+prtsc").matches(0x54));
+        assert!(!hotkey("win+printscreen").matches(0x54));
     }
 
     #[test]
     fn test_parse_hotkeys() {
         let hotkeys = parse_hotkeys(1, "test", "alt+` || alt+tab").unwrap();
         assert_eq!(hotkeys.len(), 2);
-        assert_eq!(hotkeys[0].modifier, [0x38, 0x38]);
+        assert_eq!(hotkeys[0].modifiers, [[0x38, 0xe038]]);
         assert_eq!(hotkeys[0].code, 0x29);
-        assert_eq!(hotkeys[1].modifier, [0x38, 0x38]);
+        assert_eq!(hotkeys[1].modifiers, [[0x38, 0xe038]]);
         assert_eq!(hotkeys[1].code, 0x0f);
 
         let hotkeys = parse_hotkeys(1, "test", "alt+`").unwrap();
         assert_eq!(hotkeys.len(), 1);
-        assert_eq!(hotkeys[0].modifier, [0x38, 0x38]);
+        assert_eq!(hotkeys[0].modifiers, [[0x38, 0xe038]]);
         assert_eq!(hotkeys[0].code, 0x29);
     }
+
+    #[test]
+    fn test_switch_all_windows() {
+        let config = Config::default();
+        assert!(config.switch_windows_all_hotkey.is_empty());
+        assert_eq!(config.to_hotkeys().len(), 1);
+
+        let text = "[switch-windows]\nall_hotkey = alt+q\nall_overlay = yes\n";
+        let conf = Ini::load_from_str(text).unwrap();
+        let config = Config::load(&conf).unwrap();
+        assert_eq!(
+            config.switch_windows_all_hotkey[0].id,
+            SWITCH_ALL_WINDOWS_HOTKEY_ID
+        );
+        assert_eq!(config.switch_windows_all_hotkey[0].code, 0x10);
+        assert!(config.switch_windows_all_overlay);
+        assert_eq!(config.to_hotkeys().len(), 2);
+    }
+
+    #[test]
+    fn test_launchers() {
+        let text = r#"[launchers]
+win+1 = C:\Program Files\Microsoft VS Code\Code.exe
+win+2 = "notepad.exe"
+win+3 = Microsoft.WindowsTerminal_8wekyb3d8bbwe!App
+win+foo = calc.exe
+win+4 =
+win+sc:0x3a = notepad.exe
+win+vk:0x100 = calc.exe
+"#;
+        let conf = Ini::load_from_str_opt(
+            text,
+            ParseOption {
+                enabled_escape: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let config = Config::load(&conf).unwrap();
+        let launchers: Vec<(u32, &str, bool)> = config
+            .launchers
+            .iter()
+            .map(|v| (v.hotkey.id, v.program.target.as_str(), v.program.is_aumid()))
+            .collect();
+        assert_eq!(
+            launchers,
+            [
+                (100, r"C:\Program Files\Microsoft VS Code\Code.exe", false),
+                (101, "notepad.exe", false),
+                (102, "Microsoft.WindowsTerminal_8wekyb3d8bbwe!App", true),
+                (103, "notepad.exe", false),
+            ]
+        );
+        assert_eq!(config.launchers[3].hotkey.code, 0x3a);
+        assert_eq!(config.to_hotkeys().len(), 5);
+        assert_eq!(
+            config.launchers[2].program.shell_path(),
+            r"shell:AppsFolder\Microsoft.WindowsTerminal_8wekyb3d8bbwe!App"
+        );
+        assert!(config.launchers[0]
+            .program
+            .matches_module_path(r"c:\program files\microsoft vs code\code.exe"));
+        assert!(config.launchers[1]
+            .program
+            .matches_module_path(r"C:\Windows\System32\NOTEPAD.EXE"));
+        assert!(!config.launchers[1]
+            .program
+            .matches_module_path(r"C:\Windows\notepad++.exe"));
+
+        let issues: Vec<String> = validate_config(&conf, text)
+            .into_iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "line 5: [launchers] win+foo: invalid hotkey 'win+foo'",
+                "line 6: [launchers] win+4: missing program",
+                "line 8: [launchers] win+vk:0x100: invalid hotkey 'win+vk:0x100'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pinned_apps() {
+        let text = r#"[switch-apps]
+pinned = Code.exe, "C:\Program Files\Google\Chrome\Application\chrome.exe",, Microsoft.WindowsTerminal_8wekyb3d8bbwe!App
+pinned_closed = yes
+"#;
+        let conf = Ini::load_from_str_opt(
+            text,
+            ParseOption {
+                enabled_escape: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let config = Config::load(&conf).unwrap();
+        let pinned: Vec<(&str, bool)> = config
+            .switch_apps_pinned
+            .iter()
+            .map(|v| (v.target.as_str(), v.is_aumid()))
+            .collect();
+        assert_eq!(
+            pinned,
+            [
+                ("Code.exe", false),
+                (
+                    r"C:\Program Files\Google\Chrome\Application\chrome.exe",
+                    false
+                ),
+                ("Microsoft.WindowsTerminal_8wekyb3d8bbwe!App", true),
+            ]
+        );
+        assert!(config.switch_apps_pinned_closed);
+        assert_eq!(validate_config(&conf, text), vec![]);
+    }
+
+    #[test]
+    fn test_action_keys() {
+        let text = "[switch-apps]\nclose_app = q || delete\nkill = k\nminimize =\n";
+        let conf = Ini::load_from_str(text).unwrap();
+        let config = Config::load(&conf).unwrap();
+        assert_eq!(
+            config.switch_apps_action_keys,
+            [
+                (0x10, WindowAction::CloseApp),
+                (0xe053, WindowAction::CloseApp),
+                (0x25, WindowAction::Kill),
+            ]
+        );
+        assert_eq!(parse_keys("alt+q"), None);
+        assert_eq!(parse_keys(""), Some(vec![]));
+    }
+
+    #[test]
+    fn test_position() {
+        assert_eq!(Position::parse("cursor"), Some(Position::Cursor));
+        assert_eq!(Position::parse(" foreground "), Some(Position::Foreground));
+        assert_eq!(
+            Position::parse("-1920, 40"),
+            Some(Position::Fixed(-1920, 40))
+        );
+        assert_eq!(Position::parse("100"), None);
+        assert_eq!(Position::parse("center"), None);
+
+        let conf = Ini::load_from_str("[switch-apps]\nposition = primary\n").unwrap();
+        let config = Config::load(&conf).unwrap();
+        assert_eq!(config.switch_apps_position, Position::Primary);
+    }
+
+    #[test]
+    fn test_show_delay() {
+        let text = "[switch-apps]\nshow_delay = 150\n";
+        let conf = Ini::load_from_str(text).unwrap();
+        assert_eq!(Config::load(&conf).unwrap().switch_apps_show_delay, 150);
+        assert_eq!(validate_config(&conf, text), vec![]);
+
+        let text = "[switch-apps]\nshow_delay = 150ms\n";
+        let conf = Ini::load_from_str(text).unwrap();
+        assert_eq!(Config::load(&conf).unwrap().switch_apps_show_delay, 0);
+        assert_eq!(validate_config(&conf, text).len(), 1);
+    }
+
+    #[test]
+    fn test_theme() {
+        assert_eq!(parse_color("#1e1e1e"), Some(0xff1e1e1e));
+        assert_eq!(parse_color(" #801E1E1E "), Some(0x801e1e1e));
+        assert_eq!(parse_color("1e1e1e"), None);
+        assert_eq!(parse_color("#1e1e1"), None);
+        assert_eq!(parse_color("#+e1e1e"), None);
+        assert_eq!(parse_percent("80%"), Some(80));
+        assert_eq!(parse_percent("0"), None);
+        assert_eq!(parse_percent("101"), None);
+
+        let text = "[theme]\ndark_background = #202020\nlight_border = #40000000\nopacity = 90\ncorner_radius = 0\nicon_size = 48\n";
+        let conf = Ini::load_from_str(text).unwrap();
+        let theme = Config::load(&conf).unwrap().theme;
+        assert_eq!(theme.dark.background, 0xff202020);
+        assert_eq!(theme.dark.selection, Theme::default().dark.selection);
+        assert_eq!(theme.light.border, 0x40000000);
+        assert_eq!(theme.opacity, 90);
+        assert_eq!(theme.corner_radius, Some(0));
+        assert_eq!(theme.icon_size, 48);
+        assert_eq!(theme.colors(false), &theme.dark);
+    }
+
+    #[test]
+    fn test_rules() {
+        let text = "[rules]\ninclude = exe=slack.exe; style=topmost\nexclude = title=(\nexclude = class=TrayWnd\n";
+        let conf = Ini::load_from_str_opt(
+            text,
+            ParseOption {
+                enabled_escape: false,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let config = Config::load(&conf).unwrap();
+        assert_eq!(
+            config.rules,
+            [
+                Rule::parse(RuleAction::Include, "exe=slack.exe; style=topmost").unwrap(),
+                Rule::parse(RuleAction::Exclude, "class=TrayWnd").unwrap(),
+            ]
+        );
+        let issues: Vec<String> = validate_config(&conf, text)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(issues, ["line 3: [rules] exclude: invalid title regex '('"]);
+    }
+
+    #[test]
+    fn test_groups() {
+        let text = "[groups]\nsplit = exe=winword.exe; title=^(.+) - Word$\nmerge = exe=WindowsTerminal.exe, OpenConsole.exe\nmerge = style=unknown\n";
+        let conf = Ini::load_from_str(text).unwrap();
+        let config = Config::load(&conf).unwrap();
+        assert_eq!(
+            config.groups,
+            [
+                GroupRule::parse(GroupAction::Split, "exe=winword.exe; title=^(.+) - Word$")
+                    .unwrap(),
+                GroupRule::parse(
+                    GroupAction::Merge,
+                    "exe=WindowsTerminal.exe, OpenConsole.exe"
+                )
+                .unwrap(),
+            ]
+        );
+
+        let conf = Ini::load_from_str("[groups]\nby_aumid = yes\nmerge = exe=java.exe\n").unwrap();
+        let config = Config::load(&conf).unwrap();
+        assert_eq!(
+            config.groups,
+            [
+                GroupRule::parse(GroupAction::Merge, "exe=java.exe").unwrap(),
+                GroupRule::parse(GroupAction::Aumid, "exe=*").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_default_config() {
+        let conf = Ini::load_from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(validate_config(&conf, DEFAULT_CONFIG), vec![]);
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let text = r"trayicon = maybe
+
+[switch-windows]
+hotkey = alt+` || alt+foo
+only_current_desktop = auto
+
+[switch-apps]
+enable = yes
+override_icons = a.exe=a.ico,b.exe
+hotkey = alt+tab
+ignore_minimal = yes
+ignore_minimal = nope
+
+[log]
+lvl = info
+
+[unknown]
+
+[theme]
+light_background = grey
+";
+        let conf = Ini::load_from_str(text).unwrap();
+        let issues: Vec<String> = validate_config(&conf, text)
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "line 1: trayicon: invalid boolean 'maybe', expected yes/no/true/false/on/off/1/0",
+                "line 4: [switch-windows] hotkey: invalid hotkey 'alt+foo'",
+                "line 9: [switch-apps] override_icons: malformed entry 'b.exe', expected app.exe=icon.ico",
+                "line 12: [switch-apps] ignore_minimal: invalid boolean 'nope', expected yes/no/true/false/on/off/1/0",
+                "line 15: [log] lvl: unknown key",
+                "line 17: [unknown] unknown section",
+                "line 20: [theme] light_background: invalid color 'grey', expected #RRGGBB or #AARRGGBB",
+            ]
+        );
+
+        let config = Config::load(&conf).unwrap();
+        assert!(config.trayicon);
+        assert_eq!(
+            config.switch_windows_hotkey,
+            Config::default().switch_windows_hotkey
+        );
+    }
 }
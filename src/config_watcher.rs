@@ -0,0 +1,108 @@
+use crate::app::WM_USER_RELOAD_CONFIG;
+use crate::utils::to_wstring;
+
+use anyhow::{anyhow, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+    time::{Duration, SystemTime},
+};
+use windows::core::PCWSTR;
+use windows::Win32::{
+    Foundation::{CloseHandle, HANDLE, HWND, LPARAM, WAIT_OBJECT_0, WPARAM},
+    Storage::FileSystem::{
+        FindCloseChangeNotification, FindFirstChangeNotificationW, FindNextChangeNotification,
+        FILE_NOTIFY_CHANGE_FILE_NAME, FILE_NOTIFY_CHANGE_LAST_WRITE,
+    },
+    System::Threading::{CreateEventW, SetEvent, WaitForMultipleObjects, INFINITE},
+    UI::WindowsAndMessaging::PostMessageW,
+};
+
+/// Editors often save a file in several steps, so wait a little before reading it.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches the config file and posts `WM_USER_RELOAD_CONFIG` when it changes.
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    stop_event: HANDLE,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ConfigWatcher {
+    pub fn init(hwnd: HWND, path: &Path) -> Result<Self> {
+        let folder = path
+            .parent()
+            .ok_or_else(|| anyhow!("Failed to get config folder"))?;
+        let folder = to_wstring(&folder.to_string_lossy());
+        let change = unsafe {
+            FindFirstChangeNotificationW(
+                PCWSTR(folder.as_ptr()),
+                false,
+                FILE_NOTIFY_CHANGE_LAST_WRITE | FILE_NOTIFY_CHANGE_FILE_NAME,
+            )
+        }
+        .map_err(|err| anyhow!("Failed to watch config file, {err}"))?;
+        let stop_event = match unsafe { CreateEventW(None, true, false, None) } {
+            Ok(v) => v,
+            Err(err) => {
+                let _ = unsafe { FindCloseChangeNotification(change) };
+                return Err(anyhow!("Failed to watch config file, {err}"));
+            }
+        };
+
+        let path = path.to_path_buf();
+        let (hwnd, change, stop) = (hwnd.0 as isize, change.0 as isize, stop_event.0 as isize);
+        let thread = std::thread::spawn(move || {
+            let (change, stop) = (HANDLE(change as _), HANDLE(stop as _));
+            watch(HWND(hwnd as _), path, change, stop);
+            let _ = unsafe { FindCloseChangeNotification(change) };
+        });
+        info!("config watcher start");
+
+        Ok(Self {
+            stop_event,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        debug!("config watcher destroyed");
+        unsafe {
+            let _ = SetEvent(self.stop_event);
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        unsafe {
+            let _ = CloseHandle(self.stop_event);
+        }
+    }
+}
+
+fn watch(hwnd: HWND, path: PathBuf, change: HANDLE, stop: HANDLE) {
+    let mut last_modified = get_modified(&path);
+    loop {
+        let ret = unsafe { WaitForMultipleObjects(&[change, stop], false, INFINITE) };
+        if ret != WAIT_OBJECT_0 {
+            break;
+        }
+        std::thread::sleep(DEBOUNCE);
+        let modified = get_modified(&path);
+        if modified != last_modified {
+            debug!("config file changed");
+            last_modified = modified;
+            let _ =
+                unsafe { PostMessageW(Some(hwnd), WM_USER_RELOAD_CONFIG, WPARAM(0), LPARAM(0)) };
+        }
+        if unsafe { FindNextChangeNotification(change) }.is_err() {
+            break;
+        }
+    }
+}
+
+fn get_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|v| v.modified()).ok()
+}
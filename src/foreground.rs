@@ -1,20 +1,25 @@
+use crate::mru::MruHistory;
 use crate::utils::get_window_exe;
 use anyhow::{bail, Result};
-use once_cell::sync::OnceCell;
-use std::collections::HashSet;
+use parking_lot::{Mutex, RwLock};
+use std::{collections::HashSet, sync::LazyLock};
 use windows::Win32::{
     Foundation::HWND,
     UI::{
         Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK},
         WindowsAndMessaging::{
-            EVENT_SYSTEM_FOREGROUND, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS,
+            GetForegroundWindow, EVENT_SYSTEM_FOREGROUND, OBJID_WINDOW, WINEVENT_OUTOFCONTEXT,
+            WINEVENT_SKIPOWNPROCESS,
         },
     },
 };
 
 pub static mut IS_FOREGROUND_IN_BLACKLIST: bool = false;
 
-static BLACKLIST: OnceCell<HashSet<String>> = OnceCell::new();
+static BLACKLIST: RwLock<Option<HashSet<String>>> = RwLock::new(None);
+
+/// Every window that became the foreground window, shared with the switcher.
+pub static MRU_HISTORY: LazyLock<Mutex<MruHistory>> = LazyLock::new(Default::default);
 
 #[derive(Debug)]
 pub struct ForegroundWatcher {
@@ -23,14 +28,6 @@ pub struct ForegroundWatcher {
 
 impl ForegroundWatcher {
     pub fn init(blacklist: &HashSet<String>) -> Result<Self> {
-        if blacklist.is_empty() {
-            return Ok(Self {
-                hook: HWINEVENTHOOK::default(),
-            });
-        }
-
-        let _ = BLACKLIST.set(blacklist.iter().map(|v| v.to_lowercase()).collect());
-
         let hook = unsafe {
             SetWinEventHook(
                 EVENT_SYSTEM_FOREGROUND,
@@ -45,6 +42,7 @@ impl ForegroundWatcher {
         if hook.is_invalid() {
             bail!("Failed to watch foreground");
         }
+        Self::set_blacklist(blacklist);
 
         info!("foreground watcher start");
 
@@ -52,6 +50,17 @@ impl ForegroundWatcher {
     }
 }
 
+impl ForegroundWatcher {
+    /// Replaces the blacklist shared with the event hook.
+    pub fn set_blacklist(blacklist: &HashSet<String>) {
+        *BLACKLIST.write() = Some(blacklist.iter().map(|v| v.to_lowercase()).collect());
+        // the foreground window may be on the new blacklist without changing
+        let hwnd = unsafe { GetForegroundWindow() };
+        let is_in_blacklist = is_in_blacklist(hwnd).unwrap_or_default();
+        unsafe { IS_FOREGROUND_IN_BLACKLIST = is_in_blacklist };
+    }
+}
+
 impl Drop for ForegroundWatcher {
     fn drop(&mut self) {
         debug!("foreground watcher destroyed");
@@ -67,16 +76,30 @@ unsafe extern "system" fn win_event_proc(
     _h_win_event_hook: HWINEVENTHOOK,
     _event: u32,
     hwnd: HWND,
-    _id_object: i32,
+    id_object: i32,
     _id_child: i32,
     _dw_event_thread: u32,
     _dwms_event_time: u32,
 ) {
-    let exe = match get_window_exe(hwnd) {
-        Some(v) => v.to_lowercase(),
-        None => return,
-    };
-    let is_in_blacklist = BLACKLIST.get().unwrap().contains(&exe);
-    IS_FOREGROUND_IN_BLACKLIST = is_in_blacklist;
+    if id_object == OBJID_WINDOW.0 {
+        MRU_HISTORY.lock().touch(hwnd);
+    }
+    if let Some(is_in_blacklist) = is_in_blacklist(hwnd) {
+        IS_FOREGROUND_IN_BLACKLIST = is_in_blacklist;
+    }
+}
+
+/// Whether the exe of the window is on the blacklist, `None` if it can't be told.
+fn is_in_blacklist(hwnd: HWND) -> Option<bool> {
+    if BLACKLIST.read().as_ref().is_none_or(|v| v.is_empty()) {
+        return Some(false);
+    }
+    let exe = get_window_exe(hwnd)?.to_lowercase();
+    let is_in_blacklist = BLACKLIST
+        .read()
+        .as_ref()
+        .map(|v| v.contains(&exe))
+        .unwrap_or_default();
     debug!("foreground {exe} {is_in_blacklist}");
+    Some(is_in_blacklist)
 }
@@ -0,0 +1,60 @@
+/// Scores how well `query` matches `text`, or `None` if it does not match at all.
+///
+/// The characters of the query must appear in the text in order, ignoring case and whitespace.
+/// Matches at the start of the text or of a word and consecutive characters score higher,
+/// gaps between the matched characters score lower.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    let mut score = 0;
+    let mut start = 0;
+    let mut previous: Option<usize> = None;
+    for c in query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        let index = start + text[start..].iter().position(|v| *v == c)?;
+        score += 1;
+        if index == 0 {
+            score += 10;
+        } else if !text[index - 1].is_alphanumeric() {
+            score += 8;
+        }
+        if let Some(previous) = previous {
+            if index == previous + 1 {
+                score += 5;
+            } else {
+                score -= (index - previous - 1).min(3) as i32;
+            }
+        }
+        previous = Some(index);
+        start = index + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match() {
+        assert_eq!(fuzzy_score("", "code"), Some(0));
+        assert!(fuzzy_score("vsc", "Visual Studio Code").is_some());
+        assert!(fuzzy_score("VS CODE", "Visual Studio Code").is_some());
+        assert!(fuzzy_score("code", "Visual Studio").is_none());
+        assert!(fuzzy_score("edoc", "code").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_rank() {
+        let score = |query, text| fuzzy_score(query, text).unwrap();
+        // prefix beats the middle of a word
+        assert!(score("no", "notepad") > score("no", "anote"));
+        // word starts beat scattered characters
+        assert!(score("vsc", "Visual Studio Code") > score("vsc", "devscope"));
+        // consecutive characters beat gaps
+        assert!(score("term", "terminal") > score("term", "thermometer"));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::win32::HWND;
+use anyhow::{anyhow, Result};
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// Requests are one JSON object per line, e.g. `{"command": "switch-apps", "reverse": true}`.
+///
+/// Each one is answered by a line with `{"ok": true}`, plus `"data"` for commands
+/// returning something, or `{"ok": false, "error": "..."}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum Command {
+    /// Switches to the next app at once, without showing the switcher.
+    SwitchApps {
+        reverse: bool,
+    },
+    /// Switches to the next window of the current app.
+    SwitchWindows {
+        reverse: bool,
+    },
+    /// Shows the app switcher until an app is picked with Enter or a click, or Escape.
+    ShowApps,
+    /// Returns the windows of every app, the same ones the switchers list.
+    ListWindows,
+    ReloadConfig,
+    /// Enables or disables starting with Windows, and returns whether it is enabled.
+    Startup {
+        enable: Option<bool>,
+    },
+    Quit,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl Command {
+    pub const NAMES: [&'static str; 7] = [
+        "switch-apps",
+        "switch-windows",
+        "show-apps",
+        "list-windows",
+        "reload-config",
+        "startup",
+        "quit",
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::SwitchApps { .. } => "switch-apps",
+            Command::SwitchWindows { .. } => "switch-windows",
+            Command::ShowApps => "show-apps",
+            Command::ListWindows => "list-windows",
+            Command::ReloadConfig => "reload-config",
+            Command::Startup { .. } => "startup",
+            Command::Quit => "quit",
+        }
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let value: Value =
+            serde_json::from_str(line).map_err(|err| format!("invalid request, {err}"))?;
+        let name = value
+            .get("command")
+            .and_then(Value::as_str)
+            .ok_or_else(|| "invalid request, missing command".to_string())?;
+        let get_bool = |key: &str| match value.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::Bool(v)) => Ok(Some(*v)),
+            Some(v) => Err(format!("invalid {key} '{v}', expected true or false")),
+        };
+        let command = match name {
+            "switch-apps" => Command::SwitchApps {
+                reverse: get_bool("reverse")?.unwrap_or_default(),
+            },
+            "switch-windows" => Command::SwitchWindows {
+                reverse: get_bool("reverse")?.unwrap_or_default(),
+            },
+            "show-apps" => Command::ShowApps,
+            "list-windows" => Command::ListWindows,
+            "reload-config" => Command::ReloadConfig,
+            "startup" => Command::Startup {
+                enable: get_bool("enable")?,
+            },
+            "quit" => Command::Quit,
+            _ => {
+                return Err(format!(
+                    "unknown command '{name}', expected one of {}",
+                    Command::NAMES.join(", ")
+                ))
+            }
+        };
+        Ok(command)
+    }
+
+    /// The request line of the command, without the line break.
+    pub fn to_request(self) -> String {
+        let mut value = json!({ "command": self.name() });
+        match self {
+            Command::SwitchApps { reverse: true } | Command::SwitchWindows { reverse: true } => {
+                value["reverse"] = Value::Bool(true);
+            }
+            Command::Startup {
+                enable: Some(enable),
+            } => value["enable"] = Value::Bool(enable),
+            _ => {}
+        }
+        value.to_string()
+    }
+}
+
+/// The data of `list-windows`, every window with the key of its app, in the switcher order.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn windows_data(windows: &IndexMap<String, Vec<(HWND, String)>>) -> Value {
+    windows
+        .iter()
+        .flat_map(|(app, hwnds)| {
+            hwnds.iter().map(
+                move |(hwnd, title)| json!({ "hwnd": hwnd.0 as isize, "title": title, "app": app }),
+            )
+        })
+        .collect()
+}
+
+/// Answers the requests of a connection until it is closed, `handle` runs each command
+/// and returns the data of the response, if any.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn serve<S: Read + Write>(
+    stream: S,
+    mut handle: impl FnMut(Command) -> Result<Option<Value>>,
+) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match Command::parse(&line) {
+            Ok(command) => {
+                debug!("ipc command {command:?}");
+                match handle(command) {
+                    Ok(Some(data)) => json!({ "ok": true, "data": data }),
+                    Ok(None) => json!({ "ok": true }),
+                    Err(err) => json!({ "ok": false, "error": err.to_string() }),
+                }
+            }
+            Err(err) => json!({ "ok": false, "error": err }),
+        };
+        let stream = reader.get_mut();
+        writeln!(stream, "{response}")?;
+        stream.flush()?;
+    }
+}
+
+/// Sends the command over a connection and returns the data of the response.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn request<S: Read + Write>(stream: S, command: Command) -> Result<Option<Value>> {
+    let mut reader = BufReader::new(stream);
+    let stream = reader.get_mut();
+    writeln!(stream, "{}", command.to_request())?;
+    stream.flush()?;
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(anyhow!("Window switcher closed the connection"));
+    }
+    let mut response: Value =
+        serde_json::from_str(&line).map_err(|err| anyhow!("Invalid response, {err}"))?;
+    if response["ok"] == Value::Bool(true) {
+        return Ok(response.get_mut("data").map(Value::take));
+    }
+    match response["error"].as_str() {
+        Some(err) => Err(anyhow!("{err}")),
+        None => Err(anyhow!("Invalid response, {line}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::bail;
+    use std::io::Cursor;
+
+    /// An in-memory connection, reading the requests and collecting the responses.
+    struct Stream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Stream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Stream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn requests(input: &str, handle: impl FnMut(Command) -> Result<Option<Value>>) -> Vec<Value> {
+        let mut stream = Stream {
+            input: Cursor::new(input.as_bytes().to_vec()),
+            output: vec![],
+        };
+        serve(&mut stream, handle).unwrap();
+        String::from_utf8(stream.output)
+            .unwrap()
+            .lines()
+            .map(|v| serde_json::from_str(v).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            Command::parse(r#"{"command": "switch-apps"}"#),
+            Ok(Command::SwitchApps { reverse: false })
+        );
+        assert_eq!(
+            Command::parse(r#"{"command": "switch-windows", "reverse": true}"#),
+            Ok(Command::SwitchWindows { reverse: true })
+        );
+        assert_eq!(Command::parse(r#"{"command": "quit"}"#), Ok(Command::Quit));
+        assert_eq!(
+            Command::parse(r#"{"command": "startup"}"#),
+            Ok(Command::Startup { enable: None })
+        );
+        assert!(Command::parse(r#"{"command": "switch-apps", "reverse": 1}"#).is_err());
+        assert!(Command::parse(r#"{"reverse": true}"#).is_err());
+        assert!(Command::parse("switch-apps").is_err());
+        assert_eq!(
+            Command::parse(r#"{"command": "foo"}"#),
+            Err("unknown command 'foo', expected one of switch-apps, switch-windows, show-apps, list-windows, reload-config, startup, quit".into())
+        );
+        for command in [
+            Command::SwitchApps { reverse: true },
+            Command::SwitchWindows { reverse: false },
+            Command::ShowApps,
+            Command::Startup {
+                enable: Some(false),
+            },
+            Command::Startup { enable: None },
+        ] {
+            assert_eq!(Command::parse(&command.to_request()), Ok(command));
+        }
+    }
+
+    #[test]
+    fn test_request() {
+        let response = |input: &str, command: Command| {
+            let mut stream = Stream {
+                input: Cursor::new(input.as_bytes().to_vec()),
+                output: vec![],
+            };
+            let response = request(&mut stream, command);
+            (response, String::from_utf8(stream.output).unwrap())
+        };
+        let (data, output) = response(
+            "{\"ok\": true, \"data\": {\"enabled\": true}}\n",
+            Command::Startup { enable: Some(true) },
+        );
+        assert_eq!(data.unwrap(), Some(json!({ "enabled": true })));
+        assert_eq!(output, "{\"command\":\"startup\",\"enable\":true}\n");
+        let (data, _) = response("{\"ok\": true}\n", Command::Quit);
+        assert_eq!(data.unwrap(), None);
+        let (data, _) = response("{\"ok\": false, \"error\": \"Oops\"}\n", Command::Quit);
+        assert_eq!(data.unwrap_err().to_string(), "Oops");
+        let (data, _) = response("", Command::Quit);
+        assert!(data.is_err());
+    }
+
+    #[test]
+    fn test_windows_data() {
+        let mut windows = IndexMap::new();
+        windows.insert(
+            r"C:\Code.exe".to_string(),
+            vec![
+                (HWND(1 as _), "a.rs".to_string()),
+                (HWND(3 as _), "b.rs".to_string()),
+            ],
+        );
+        windows.insert(
+            r"C:\notepad.exe".to_string(),
+            vec![(HWND(2 as _), "todo.txt".to_string())],
+        );
+        assert_eq!(
+            windows_data(&windows),
+            json!([
+                { "hwnd": 1, "title": "a.rs", "app": r"C:\Code.exe" },
+                { "hwnd": 3, "title": "b.rs", "app": r"C:\Code.exe" },
+                { "hwnd": 2, "title": "todo.txt", "app": r"C:\notepad.exe" },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_serve() {
+        let mut commands = vec![];
+        let input = concat!(
+            "{\"command\": \"switch-apps\", \"reverse\": true}\n",
+            "\n",
+            "{\"command\": \"list-windows\"}\r\n",
+            "{\"command\": \"foo\"}\n",
+            "{\"command\": \"reload-config\"}",
+        );
+        let responses = requests(input, |command| {
+            commands.push(command);
+            match command {
+                Command::ListWindows => Ok(Some(json!([{ "title": "a.rs" }]))),
+                Command::ReloadConfig => bail!("Failed to load config"),
+                _ => Ok(None),
+            }
+        });
+        assert_eq!(
+            commands,
+            [
+                Command::SwitchApps { reverse: true },
+                Command::ListWindows,
+                Command::ReloadConfig,
+            ]
+        );
+        assert_eq!(
+            responses,
+            [
+                json!({ "ok": true }),
+                json!({ "ok": true, "data": [{ "title": "a.rs" }] }),
+                json!({ "ok": false, "error": "unknown command 'foo', expected one of switch-apps, switch-windows, show-apps, list-windows, reload-config, startup, quit" }),
+                json!({ "ok": false, "error": "Failed to load config" }),
+            ]
+        );
+    }
+}
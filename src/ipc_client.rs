@@ -0,0 +1,51 @@
+use crate::ipc::{self, Command};
+use crate::ipc_server::pipe_name;
+use crate::utils::to_wstring;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::{fs::File, os::windows::io::FromRawHandle};
+use windows::core::PCWSTR;
+use windows::Win32::{
+    Foundation::{ERROR_FILE_NOT_FOUND, ERROR_PIPE_BUSY, GENERIC_READ, GENERIC_WRITE},
+    Storage::FileSystem::{CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_NONE, OPEN_EXISTING},
+    System::Pipes::WaitNamedPipeW,
+};
+
+/// How long to wait for the server to finish with another client.
+const CONNECT_TIMEOUT: u32 = 5000;
+
+/// Sends the command to the running instance and returns the data of its response.
+pub fn send_command(command: Command) -> Result<Option<Value>> {
+    let pipe = connect()?;
+    ipc::request(pipe, command)
+}
+
+fn connect() -> Result<File> {
+    let name = to_wstring(&pipe_name());
+    loop {
+        let ret = unsafe {
+            CreateFileW(
+                PCWSTR(name.as_ptr()),
+                (GENERIC_READ | GENERIC_WRITE).0,
+                FILE_SHARE_NONE,
+                None,
+                OPEN_EXISTING,
+                FILE_FLAGS_AND_ATTRIBUTES(0),
+                None,
+            )
+        };
+        match ret {
+            Ok(handle) => return Ok(unsafe { File::from_raw_handle(handle.0) }),
+            Err(err) if err.code() == ERROR_FILE_NOT_FOUND.to_hresult() => {
+                return Err(anyhow!("Window switcher is not running"));
+            }
+            Err(err) if err.code() == ERROR_PIPE_BUSY.to_hresult() => {
+                if !unsafe { WaitNamedPipeW(PCWSTR(name.as_ptr()), CONNECT_TIMEOUT) }.as_bool() {
+                    return Err(anyhow!("Window switcher is busy"));
+                }
+            }
+            Err(err) => return Err(anyhow!("Failed to connect to window switcher, {err}")),
+        }
+    }
+}
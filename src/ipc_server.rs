@@ -0,0 +1,261 @@
+use crate::app::WM_USER_IPC;
+use crate::ipc::{self, Command};
+use crate::utils::{to_wstring, HandleWrapper};
+
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::{io, sync::Arc, thread::JoinHandle};
+use windows::core::PCWSTR;
+use windows::Win32::{
+    Foundation::{
+        CloseHandle, ERROR_BROKEN_PIPE, ERROR_IO_PENDING, ERROR_OPERATION_ABORTED,
+        ERROR_PIPE_CONNECTED, ERROR_TIMEOUT, HANDLE, HWND, LPARAM, WAIT_OBJECT_0, WPARAM,
+    },
+    Storage::FileSystem::{
+        ReadFile, WriteFile, FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_FLAG_OVERLAPPED,
+        PIPE_ACCESS_DUPLEX,
+    },
+    System::{
+        Pipes::{
+            ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_BYTE,
+            PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_WAIT,
+        },
+        Threading::{
+            CreateEventW, SetEvent, WaitForMultipleObjects, WaitForSingleObject, INFINITE,
+        },
+        IO::{CancelIoEx, GetOverlappedResult, OVERLAPPED},
+    },
+    UI::WindowsAndMessaging::{SendMessageTimeoutW, SMTO_NORMAL},
+};
+
+/// Long enough to list the windows, short enough not to hang on exit when a
+/// request comes in while the window thread waits for the server to stop.
+const REQUEST_TIMEOUT: u32 = 5000;
+
+const BUFFER_SIZE: u32 = 4096;
+
+/// The pipe of the current user, so that instances in other sessions don't clash.
+pub fn pipe_name() -> String {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    format!(r"\\.\pipe\window-switcher-{user}")
+}
+
+/// A command sent to the window thread, which fills in the response.
+#[derive(Debug)]
+pub struct IpcRequest {
+    pub command: Command,
+    pub response: Mutex<Option<Result<Option<Value>>>>,
+}
+
+/// Serves the commands of [`ipc`] on a named pipe, one client at a time, and runs
+/// them on the window thread with `WM_USER_IPC`.
+#[derive(Debug)]
+pub struct IpcServer {
+    stop_event: HANDLE,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl IpcServer {
+    pub fn init(hwnd: HWND) -> Result<Self> {
+        let stop_event = unsafe { CreateEventW(None, true, false, None) }
+            .map_err(|err| anyhow!("Failed to start ipc server, {err}"))?;
+        let (hwnd, stop) = (hwnd.0 as isize, stop_event.0 as isize);
+        let thread = std::thread::spawn(move || {
+            listen(HWND(hwnd as _), HANDLE(stop as _));
+        });
+        info!("ipc server start");
+
+        Ok(Self {
+            stop_event,
+            thread: Some(thread),
+        })
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        debug!("ipc server destroyed");
+        unsafe {
+            let _ = SetEvent(self.stop_event);
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        unsafe {
+            let _ = CloseHandle(self.stop_event);
+        }
+    }
+}
+
+fn listen(hwnd: HWND, stop: HANDLE) {
+    let name = to_wstring(&pipe_name());
+    loop {
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                PCWSTR(name.as_ptr()),
+                PIPE_ACCESS_DUPLEX | FILE_FLAG_OVERLAPPED | FILE_FLAG_FIRST_PIPE_INSTANCE,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                1,
+                BUFFER_SIZE,
+                BUFFER_SIZE,
+                0,
+                None,
+            )
+        };
+        if pipe.is_invalid() {
+            error!(
+                "Failed to create ipc pipe, {}",
+                windows::core::Error::from_win32()
+            );
+            return;
+        }
+        let pipe = HandleWrapper::new(pipe);
+        let mut stream = match PipeStream::new(pipe.get_handle(), stop) {
+            Ok(v) => v,
+            Err(err) => {
+                error!("Failed to create ipc pipe, {err}");
+                return;
+            }
+        };
+        match stream.connect() {
+            Ok(()) => {
+                debug!("ipc client connected");
+                if let Err(err) = ipc::serve(&mut stream, |command| send_request(hwnd, command)) {
+                    debug!("ipc client lost, {err}");
+                }
+                let _ = unsafe { DisconnectNamedPipe(pipe.get_handle()) };
+            }
+            Err(err) if err.code() == ERROR_OPERATION_ABORTED.to_hresult() => {}
+            Err(err) => {
+                error!("Failed to connect ipc client, {err}");
+                return;
+            }
+        }
+        if unsafe { WaitForSingleObject(stop, 0) } == WAIT_OBJECT_0 {
+            return;
+        }
+    }
+}
+
+/// Runs the command on the window thread and waits for its response.
+fn send_request(hwnd: HWND, command: Command) -> Result<Option<Value>> {
+    let request = Arc::new(IpcRequest {
+        command,
+        response: Mutex::new(None),
+    });
+    // the window thread takes this reference, even if it gets to the message after the timeout
+    let ptr = Arc::into_raw(request.clone());
+    let mut result: usize = 0;
+    let ret = unsafe {
+        SendMessageTimeoutW(
+            hwnd,
+            WM_USER_IPC,
+            WPARAM(0),
+            LPARAM(ptr as isize),
+            SMTO_NORMAL,
+            REQUEST_TIMEOUT,
+            Some(&mut result as *mut _ as *mut _),
+        )
+    };
+    if ret.0 == 0 {
+        let err = windows::core::Error::from_win32();
+        // after a timeout the message stays queued, any other failure means it was never sent
+        if err.code() != ERROR_TIMEOUT.to_hresult() {
+            drop(unsafe { Arc::from_raw(ptr) });
+            return Err(anyhow!("Failed to send ipc request, {err}"));
+        }
+    }
+    let response = request.response.lock().take();
+    response.unwrap_or_else(|| Err(anyhow!("Window switcher did not respond")))
+}
+
+/// A connection of the pipe, whose I/O is abandoned as soon as the server stops.
+struct PipeStream {
+    pipe: HANDLE,
+    stop: HANDLE,
+    event: HandleWrapper,
+}
+
+impl PipeStream {
+    fn new(pipe: HANDLE, stop: HANDLE) -> windows::core::Result<Self> {
+        let event = unsafe { CreateEventW(None, true, false, None) }?;
+        Ok(Self {
+            pipe,
+            stop,
+            event: HandleWrapper::new(event),
+        })
+    }
+
+    fn connect(&mut self) -> windows::core::Result<()> {
+        let mut overlapped = self.overlapped();
+        let started = unsafe { ConnectNamedPipe(self.pipe, Some(&mut overlapped)) };
+        match started {
+            Err(err) if err.code() == ERROR_PIPE_CONNECTED.to_hresult() => Ok(()),
+            started => self.complete(&overlapped, started).map(|_| ()),
+        }
+    }
+
+    fn overlapped(&self) -> OVERLAPPED {
+        OVERLAPPED {
+            hEvent: self.event.get_handle(),
+            ..Default::default()
+        }
+    }
+
+    /// Waits for the I/O to complete, or cancels it when the server stops.
+    fn complete(
+        &self,
+        overlapped: &OVERLAPPED,
+        started: windows::core::Result<()>,
+    ) -> windows::core::Result<u32> {
+        if let Err(err) = started {
+            if err.code() != ERROR_IO_PENDING.to_hresult() {
+                return Err(err);
+            }
+        }
+        let mut transferred = 0;
+        let ret = unsafe {
+            WaitForMultipleObjects(&[self.event.get_handle(), self.stop], false, INFINITE)
+        };
+        if ret != WAIT_OBJECT_0 {
+            unsafe {
+                let _ = CancelIoEx(self.pipe, Some(overlapped));
+                // the I/O may still write to `overlapped` until the cancellation is done
+                let _ = GetOverlappedResult(self.pipe, overlapped, &mut transferred, true);
+            }
+            return Err(windows::core::Error::from_hresult(
+                ERROR_OPERATION_ABORTED.to_hresult(),
+            ));
+        }
+        unsafe { GetOverlappedResult(self.pipe, overlapped, &mut transferred, false) }?;
+        Ok(transferred)
+    }
+}
+
+impl io::Read for PipeStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut overlapped = self.overlapped();
+        let started = unsafe { ReadFile(self.pipe, Some(buf), None, Some(&mut overlapped)) };
+        match self.complete(&overlapped, started) {
+            Ok(n) => Ok(n as usize),
+            // the client has closed its end
+            Err(err) if err.code() == ERROR_BROKEN_PIPE.to_hresult() => Ok(0),
+            Err(err) => Err(io::Error::other(err)),
+        }
+    }
+}
+
+impl io::Write for PipeStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut overlapped = self.overlapped();
+        let started = unsafe { WriteFile(self.pipe, Some(buf), None, Some(&mut overlapped)) };
+        self.complete(&overlapped, started)
+            .map(|n| n as usize)
+            .map_err(io::Error::other)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -1,52 +1,59 @@
+use crate::config::{
+    Hotkey, WindowAction, LAUNCHER_HOTKEY_ID, MODIFIER_KEYS, SWITCH_ALL_WINDOWS_HOTKEY_ID,
+    SWITCH_APPS_HOTKEY_ID, SWITCH_WINDOWS_HOTKEY_ID,
+};
+#[cfg(windows)]
 use crate::{
     app::{
+        WM_USER_LAUNCH, WM_USER_SWITCH_ALL_WINDOWS, WM_USER_SWITCH_ALL_WINDOWS_DONE,
         WM_USER_SWITCH_APPS, WM_USER_SWITCH_APPS_CANCEL, WM_USER_SWITCH_APPS_DONE,
+        WM_USER_SWITCH_APPS_NAVIGATE, WM_USER_SWITCH_APPS_SEARCH,
+        WM_USER_SWITCH_APPS_SEARCH_BACKSPACE, WM_USER_SWITCH_APPS_WINDOW_ACTION,
         WM_USER_SWITCH_WINDOWS, WM_USER_SWITCH_WINDOWS_DONE,
     },
-    config::{Hotkey, SWITCH_APPS_HOTKEY_ID, SWITCH_WINDOWS_HOTKEY_ID},
     foreground::IS_FOREGROUND_IN_BLACKLIST,
 };
 
-use anyhow::{anyhow, Result};
 use indexmap::IndexSet;
-use parking_lot::Mutex;
-use std::sync::LazyLock;
+#[cfg(windows)]
 use windows::Win32::{
     Foundation::{HWND, LPARAM, LRESULT, WPARAM},
     System::LibraryLoader::GetModuleHandleW,
-    UI::{
-        Input::KeyboardAndMouse::{SCANCODE_LSHIFT, SCANCODE_RSHIFT},
-        WindowsAndMessaging::{
-            CallNextHookEx, SendMessageTimeoutW, SetWindowsHookExW, UnhookWindowsHookEx, HHOOK,
-            KBDLLHOOKSTRUCT, LLKHF_UP, SMTO_ABORTIFHUNG, WH_KEYBOARD_LL,
-        },
+    UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VIRTUAL_KEY,
+    },
+    UI::WindowsAndMessaging::{
+        CallNextHookEx, SendMessageTimeoutW, SetWindowsHookExW, UnhookWindowsHookEx, HHOOK,
+        KBDLLHOOKSTRUCT, LLKHF_EXTENDED, LLKHF_UP, SMTO_ABORTIFHUNG, WH_KEYBOARD_LL,
     },
 };
+#[cfg(windows)]
+use {
+    anyhow::{anyhow, Result},
+    parking_lot::Mutex,
+    std::sync::LazyLock,
+};
 
-static KEYBOARD_STATE: LazyLock<Mutex<Vec<HotKeyState>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+#[cfg(windows)]
+static KEYBOARD_STATE: LazyLock<Mutex<HotkeyStateMachine>> =
+    LazyLock::new(|| Mutex::new(HotkeyStateMachine::default()));
+#[cfg(windows)]
 static mut WINDOW: HWND = HWND(0 as _);
-static mut IS_SHIFT_PRESSED: bool = false;
-static mut IS_SWITCHING_APPS: bool = false;
-static mut PREVIOUS_KEYCODE: u32 = 0;
 
+#[cfg(windows)]
 #[derive(Debug)]
 pub struct KeyboardListener {
     hook: HHOOK,
 }
 
+#[cfg(windows)]
 impl KeyboardListener {
-    pub fn init(hwnd: HWND, hotkeys: &[&Hotkey]) -> Result<Self> {
-        unsafe { WINDOW = hwnd }
-
-        let keyboard_state = hotkeys
-            .iter()
-            .map(|hotkey| HotKeyState {
-                hotkey: (*hotkey).clone(),
-                is_modifier_pressed: false,
-            })
-            .collect();
-        *KEYBOARD_STATE.lock() = keyboard_state;
-
+    pub fn init(
+        hwnd: HWND,
+        hotkeys: &[&Hotkey],
+        action_keys: &[(u32, WindowAction)],
+        all_windows_overlay: bool,
+    ) -> Result<Self> {
         let hook = unsafe {
             let hinstance = { GetModuleHandleW(None) }
                 .map_err(|err| anyhow!("Failed to get module handle, {err}"))?;
@@ -58,12 +65,32 @@ impl KeyboardListener {
             )
         }
         .map_err(|err| anyhow!("Failed to set windows hook, {err}"))?;
+        unsafe { WINDOW = hwnd }
+        let mut state = KEYBOARD_STATE.lock();
+        *state = HotkeyStateMachine::new(hotkeys)
+            .with_action_keys(action_keys)
+            .with_all_windows_overlay(all_windows_overlay)
+            .with_held_keys(&state);
+        drop(state);
         info!("keyboard listener start");
 
         Ok(Self { hook })
     }
+
+    /// Lets the keys pick from the app switcher while no hotkey is held, see
+    /// [`HotkeyStateMachine::set_switcher_pinned`].
+    pub fn set_switcher_pinned(&self, value: bool) {
+        KEYBOARD_STATE.lock().set_switcher_pinned(value);
+    }
+
+    /// Lets typed keys through while the app switcher waits for its show delay, see
+    /// [`HotkeyStateMachine::set_switcher_hidden`].
+    pub fn set_switcher_hidden(&self, value: bool) {
+        KEYBOARD_STATE.lock().set_switcher_hidden(value);
+    }
 }
 
+#[cfg(windows)]
 impl Drop for KeyboardListener {
     fn drop(&mut self) {
         debug!("keyboard listener destroyed");
@@ -73,12 +100,305 @@ impl Drop for KeyboardListener {
     }
 }
 
-#[derive(Debug)]
-struct HotKeyState {
-    hotkey: Hotkey,
-    is_modifier_pressed: bool,
+/// What the keyboard hook should do in response to a key event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum KeyAction {
+    SwitchApps {
+        reverse: bool,
+    },
+    SwitchAppsDone,
+    SwitchAppsCancel,
+    SwitchWindows {
+        reverse: bool,
+    },
+    SwitchWindowsDone,
+    /// Cycles every window rather than the windows of the current app.
+    SwitchAllWindows {
+        reverse: bool,
+    },
+    SwitchAllWindowsDone,
+    /// Focuses or starts the program of the launcher at this index, its release sends
+    /// `SwitchWindowsDone` as it cycles windows the same way.
+    Launch(usize),
+    /// A character typed while the app switcher is open.
+    SearchInput(char),
+    SearchBackspace,
+    /// An arrow key pressed while the app switcher is open.
+    Navigate(Direction),
+    /// An action key pressed while the app switcher is open.
+    Window(WindowAction),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl Direction {
+    /// Indexed by the discriminant, which is how directions are sent in messages.
+    pub const ALL: [Direction; 4] = [
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+    ];
+
+    fn from_scan_code(scan_code: u32) -> Option<Self> {
+        match scan_code {
+            0xe048 | 0x48 => Some(Direction::Up),
+            0xe050 | 0x50 => Some(Direction::Down),
+            0xe04b | 0x4b => Some(Direction::Left),
+            0xe04d | 0x4d => Some(Direction::Right),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct KeyOutput {
+    pub actions: Vec<KeyAction>,
+    /// Whether the key event should be kept from the foreground app.
+    pub swallow: bool,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+const SCANCODE_ESCAPE: u32 = 0x01;
+#[cfg_attr(not(windows), allow(dead_code))]
+const SCANCODE_BACKSPACE: u32 = 0x0e;
+#[cfg_attr(not(windows), allow(dead_code))]
+const SCANCODE_ENTERS: [u32; 2] = [0x1c, 0xe01c];
+#[cfg_attr(not(windows), allow(dead_code))]
+const SCANCODE_SHIFTS: [u32; 2] = [0x2a, 0x36];
+#[cfg_attr(not(windows), allow(dead_code))]
+const SCANCODE_WINS: [u32; 2] = [0xe05b, 0xe05c];
+
+/// Tracks the held modifier keys and turns raw `(scan_code, is_down)` key events into [`KeyAction`]s.
+///
+/// Scan codes of extended keys are expected to be prefixed with `0xe0`.
+#[derive(Debug, Default)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct HotkeyStateMachine {
+    /// Sorted by number of modifiers, so that the most specific chord wins.
+    hotkeys: Vec<Hotkey>,
+    /// Keys acting on the selected entry while switching apps, they take precedence over searching.
+    action_keys: Vec<(u32, WindowAction)>,
+    /// Whether switching all windows shows the switcher, which then takes keys like switching apps.
+    all_windows_overlay: bool,
+    pressed_modifiers: IndexSet<u32>,
+    is_switching_apps: bool,
+    /// Whether the app switcher was shown without a hotkey, it stays open until Enter or Escape.
+    is_switcher_pinned: bool,
+    /// Whether the app switcher is open but not painted yet, keys are not searched or acted on
+    /// before it can be seen.
+    is_switcher_hidden: bool,
+    is_foreground_in_blacklist: bool,
+    previous_keycode: u32,
+    /// The character of the current key event, used to search while switching apps.
+    typed_char: Option<char>,
+    /// Keys whose key-down was swallowed, so that their key-up is swallowed too.
+    swallowed_keys: IndexSet<u32>,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl HotkeyStateMachine {
+    pub fn new(hotkeys: &[&Hotkey]) -> Self {
+        let mut hotkeys: Vec<Hotkey> = hotkeys.iter().map(|v| (*v).clone()).collect();
+        hotkeys.sort_by_key(|v| std::cmp::Reverse(v.modifiers.len()));
+        Self {
+            hotkeys,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_action_keys(mut self, action_keys: &[(u32, WindowAction)]) -> Self {
+        self.action_keys = action_keys.to_vec();
+        self
+    }
+
+    pub fn with_all_windows_overlay(mut self, value: bool) -> Self {
+        self.all_windows_overlay = value;
+        self
+    }
+
+    /// Keeps track of the keys held in `previous`, so that releasing them after the config
+    /// is reloaded still completes a hotkey.
+    pub fn with_held_keys(mut self, previous: &HotkeyStateMachine) -> Self {
+        self.pressed_modifiers = previous.pressed_modifiers.clone();
+        self.swallowed_keys = previous.swallowed_keys.clone();
+        self.previous_keycode = previous.previous_keycode;
+        self
+    }
+
+    pub fn set_switcher_pinned(&mut self, value: bool) {
+        self.is_switcher_pinned = value;
+        self.is_switching_apps = value;
+    }
+
+    pub fn set_switcher_hidden(&mut self, value: bool) {
+        self.is_switcher_hidden = value;
+    }
+
+    pub fn set_foreground_in_blacklist(&mut self, value: bool) {
+        self.is_foreground_in_blacklist = value;
+    }
+
+    pub fn set_typed_char(&mut self, value: Option<char>) {
+        self.typed_char = value;
+    }
+
+    pub fn handle(&mut self, scan_code: u32, is_down: bool) -> KeyOutput {
+        let mut output = KeyOutput::default();
+        if !is_down && self.swallowed_keys.swap_remove(&scan_code) {
+            output.swallow = true;
+        }
+
+        if MODIFIER_KEYS.contains(&scan_code) {
+            if is_down {
+                self.pressed_modifiers.insert(scan_code);
+                return output;
+            }
+            let was_pressed: Vec<bool> = self
+                .hotkeys
+                .iter()
+                .map(|v| self.is_modifier_pressed(v))
+                .collect();
+            self.pressed_modifiers.swap_remove(&scan_code);
+            let mut done_hotkeys: IndexSet<u32> = IndexSet::new();
+            for (hotkey, was_pressed) in self.hotkeys.iter().zip(was_pressed) {
+                if was_pressed
+                    && !self.is_modifier_pressed(hotkey)
+                    && hotkey.matches(self.previous_keycode)
+                {
+                    done_hotkeys.insert(hotkey.id);
+                }
+            }
+            if !done_hotkeys.is_empty() {
+                self.previous_keycode = 0;
+            }
+            for id in done_hotkeys {
+                if id == SWITCH_APPS_HOTKEY_ID {
+                    output.actions.push(KeyAction::SwitchAppsDone);
+                    self.is_switching_apps = false;
+                    self.is_switcher_pinned = false;
+                } else if id == SWITCH_WINDOWS_HOTKEY_ID || id >= LAUNCHER_HOTKEY_ID {
+                    output.actions.push(KeyAction::SwitchWindowsDone);
+                } else if id == SWITCH_ALL_WINDOWS_HOTKEY_ID {
+                    output.actions.push(KeyAction::SwitchAllWindowsDone);
+                    self.is_switching_apps = false;
+                }
+            }
+            return output;
+        }
+        if !is_down {
+            return output;
+        }
+
+        let mut action = None;
+        for hotkey in self.hotkeys.iter() {
+            if !self.is_modifier_pressed(hotkey) {
+                continue;
+            }
+            let id = hotkey.id;
+            if hotkey.matches(scan_code) {
+                // shift reverses the direction unless it is part of the hotkey
+                let reverse = !SCANCODE_SHIFTS.iter().any(|v| hotkey.has_modifier(*v))
+                    && SCANCODE_SHIFTS
+                        .iter()
+                        .any(|v| self.pressed_modifiers.contains(v));
+                if id == SWITCH_APPS_HOTKEY_ID {
+                    action = Some(KeyAction::SwitchApps { reverse });
+                } else if id == SWITCH_WINDOWS_HOTKEY_ID && !self.is_foreground_in_blacklist {
+                    action = Some(KeyAction::SwitchWindows { reverse });
+                } else if id == SWITCH_ALL_WINDOWS_HOTKEY_ID && !self.is_foreground_in_blacklist {
+                    action = Some(KeyAction::SwitchAllWindows { reverse });
+                } else if id >= LAUNCHER_HOTKEY_ID {
+                    action = Some(KeyAction::Launch((id - LAUNCHER_HOTKEY_ID) as usize));
+                }
+                if action.is_some() {
+                    self.previous_keycode = scan_code;
+                    break;
+                }
+            } else if id == SWITCH_APPS_HOTKEY_ID
+                || (id == SWITCH_ALL_WINDOWS_HOTKEY_ID && self.all_windows_overlay)
+            {
+                if scan_code == SCANCODE_ESCAPE {
+                    action = Some(KeyAction::SwitchAppsCancel);
+                    self.previous_keycode = scan_code;
+                    break;
+                } else if self.is_switching_apps {
+                    // arrow keys, both the dedicated ones and the numpad ones
+                    if let Some(direction) = Direction::from_scan_code(scan_code) {
+                        action = Some(KeyAction::Navigate(direction));
+                        break;
+                    }
+                }
+            }
+        }
+
+        if action.is_none() && self.is_switcher_pinned {
+            if scan_code == SCANCODE_ESCAPE {
+                action = Some(KeyAction::SwitchAppsCancel);
+            } else if SCANCODE_ENTERS.contains(&scan_code) {
+                action = Some(KeyAction::SwitchAppsDone);
+            } else if let Some(direction) = Direction::from_scan_code(scan_code) {
+                action = Some(KeyAction::Navigate(direction));
+            }
+        }
+
+        if action.is_none() && self.is_switching_apps && !self.is_switcher_hidden {
+            if let Some((_, window_action)) = self.action_keys.iter().find(|(v, _)| *v == scan_code)
+            {
+                action = Some(KeyAction::Window(*window_action));
+            } else if scan_code == SCANCODE_BACKSPACE {
+                action = Some(KeyAction::SearchBackspace);
+            } else if let Some(c) = self.typed_char {
+                action = Some(KeyAction::SearchInput(c));
+            }
+        }
+
+        if let Some(action) = action {
+            let is_switcher_shown =
+                matches!(action, KeyAction::SwitchAllWindows { .. }) && self.all_windows_overlay;
+            self.is_switching_apps = is_switcher_shown
+                || matches!(
+                    action,
+                    KeyAction::SwitchApps { .. }
+                        | KeyAction::SearchInput(_)
+                        | KeyAction::SearchBackspace
+                        | KeyAction::Navigate(_)
+                        | KeyAction::Window(_)
+                );
+            self.is_switcher_pinned &= self.is_switching_apps;
+            self.swallowed_keys.insert(scan_code);
+            output.actions.push(action);
+            output.swallow = true;
+        }
+        output
+    }
+
+    /// Whether a Win key is held, whose release opens the Start menu unless another key was seen.
+    pub fn is_win_pressed(&self) -> bool {
+        SCANCODE_WINS
+            .iter()
+            .any(|v| self.pressed_modifiers.contains(v))
+    }
+
+    fn is_modifier_pressed(&self, hotkey: &Hotkey) -> bool {
+        hotkey
+            .modifiers
+            .iter()
+            .all(|v| v.iter().any(|code| self.pressed_modifiers.contains(code)))
+    }
 }
 
+#[cfg(windows)]
 unsafe fn send_message_timeout(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPARAM) {
     let mut result: usize = 0;
     let _ = SendMessageTimeoutW(
@@ -92,91 +412,616 @@ unsafe fn send_message_timeout(hwnd: HWND, msg: u32, wparam: WPARAM, lparam: LPA
     );
 }
 
+#[cfg(windows)]
+unsafe fn send_action(action: KeyAction) {
+    let (msg, lparam) = match action {
+        KeyAction::SwitchApps { reverse } => (WM_USER_SWITCH_APPS, reverse as isize),
+        KeyAction::SwitchAppsDone => (WM_USER_SWITCH_APPS_DONE, 0),
+        KeyAction::SwitchAppsCancel => (WM_USER_SWITCH_APPS_CANCEL, 0),
+        KeyAction::SwitchWindows { reverse } => (WM_USER_SWITCH_WINDOWS, reverse as isize),
+        KeyAction::SwitchWindowsDone => (WM_USER_SWITCH_WINDOWS_DONE, 0),
+        KeyAction::SwitchAllWindows { reverse } => (WM_USER_SWITCH_ALL_WINDOWS, reverse as isize),
+        KeyAction::SwitchAllWindowsDone => (WM_USER_SWITCH_ALL_WINDOWS_DONE, 0),
+        KeyAction::Launch(index) => (WM_USER_LAUNCH, index as isize),
+        KeyAction::SearchInput(c) => (WM_USER_SWITCH_APPS_SEARCH, c as isize),
+        KeyAction::SearchBackspace => (WM_USER_SWITCH_APPS_SEARCH_BACKSPACE, 0),
+        KeyAction::Navigate(direction) => (WM_USER_SWITCH_APPS_NAVIGATE, direction as isize),
+        KeyAction::Window(action) => (WM_USER_SWITCH_APPS_WINDOW_ACTION, action as isize),
+    };
+    send_message_timeout(WINDOW, msg, WPARAM(0), LPARAM(lparam));
+}
+
+#[cfg(windows)]
 unsafe extern "system" fn keyboard_proc(code: i32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
     let kbd_data: &KBDLLHOOKSTRUCT = &*(l_param.0 as *const _);
     debug!("keyboard {kbd_data:?}");
-    let mut is_modifier = false;
-    let scan_code = kbd_data.scanCode;
-    let is_key_pressed = || kbd_data.flags.0 & LLKHF_UP.0 == 0;
-    if [SCANCODE_LSHIFT, SCANCODE_RSHIFT].contains(&scan_code) {
-        IS_SHIFT_PRESSED = is_key_pressed();
-    }
-    let mut keyboard_state = KEYBOARD_STATE.lock();
-    let mut send_done_hotkeys: IndexSet<u32> = IndexSet::new();
-    let mut send_action_message: Option<(u32, isize, bool)> = None;
-
-    for state in keyboard_state.iter_mut() {
-        if state.hotkey.modifier.contains(&scan_code) {
-            is_modifier = true;
-            if is_key_pressed() {
-                state.is_modifier_pressed = true;
-            } else {
-                state.is_modifier_pressed = false;
-                if PREVIOUS_KEYCODE == state.hotkey.code {
-                    send_done_hotkeys.insert(state.hotkey.id);
-                }
-            }
-        }
+    let is_down = kbd_data.flags.0 & LLKHF_UP.0 == 0;
+    let scan_code = if kbd_data.flags.0 & LLKHF_EXTENDED.0 != 0 {
+        0xe000 | kbd_data.scanCode
+    } else {
+        kbd_data.scanCode
+    };
+    let (output, is_win_pressed) = {
+        let mut keyboard_state = KEYBOARD_STATE.lock();
+        keyboard_state.set_foreground_in_blacklist(IS_FOREGROUND_IN_BLACKLIST);
+        keyboard_state.set_typed_char(vk_to_char(kbd_data.vkCode));
+        let output = keyboard_state.handle(scan_code, is_down);
+        (output, keyboard_state.is_win_pressed())
+    };
+    if is_down && output.swallow && is_win_pressed {
+        mask_win_key();
     }
-    if !is_modifier {
-        for state in keyboard_state.iter_mut() {
-            if is_key_pressed() && state.is_modifier_pressed {
-                let id = state.hotkey.id;
-                if scan_code == state.hotkey.code {
-                    let reverse = if IS_SHIFT_PRESSED { 1 } else { 0 };
-                    if id == SWITCH_APPS_HOTKEY_ID
-                        || (id == SWITCH_WINDOWS_HOTKEY_ID && !IS_FOREGROUND_IN_BLACKLIST)
-                    {
-                        send_action_message = Some((id, reverse, false));
-                        PREVIOUS_KEYCODE = scan_code;
-                        break;
-                    };
-                } else if id == SWITCH_APPS_HOTKEY_ID {
-                    if scan_code == 0x01 {
-                        // escape key
-                        send_action_message = Some((id, 0, true));
-                        PREVIOUS_KEYCODE = scan_code;
-                        break;
-                    } else if [0x48, 0x4b, 0x4d, 0x50].contains(&scan_code) && IS_SWITCHING_APPS {
-                        // arrow keys
-                        let reverse = if scan_code == 0x48 || scan_code == 0x4b {
-                            1
-                        } else {
-                            0
-                        };
-                        send_action_message = Some((id, reverse, false));
-                        break;
-                    }
-                }
-            }
-        }
+    for action in output.actions {
+        send_action(action);
+    }
+    if output.swallow {
+        return LRESULT(1);
     }
-    drop(keyboard_state);
+    CallNextHookEx(None, code, w_param, l_param)
+}
 
-    for id in send_done_hotkeys {
-        if id == SWITCH_APPS_HOTKEY_ID {
-            send_message_timeout(WINDOW, WM_USER_SWITCH_APPS_DONE, WPARAM(0), LPARAM(0));
-            IS_SWITCHING_APPS = false;
-        } else if id == SWITCH_WINDOWS_HOTKEY_ID {
-            send_message_timeout(WINDOW, WM_USER_SWITCH_WINDOWS_DONE, WPARAM(0), LPARAM(0));
+/// Sends an unassigned key, so that releasing Win after a swallowed hotkey doesn't open the Start menu.
+#[cfg(windows)]
+unsafe fn mask_win_key() {
+    let input = |flags| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: VIRTUAL_KEY(0xe8),
+                dwFlags: flags,
+                ..Default::default()
+            },
+        },
+    };
+    let inputs = [input(Default::default()), input(KEYEVENTF_KEYUP)];
+    SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+}
+
+/// Maps letters, digits and a few punctuation keys to the character used for searching.
+///
+/// Virtual-key codes follow the keyboard layout, unlike scan codes.
+#[cfg(windows)]
+fn vk_to_char(vk: u32) -> Option<char> {
+    match vk {
+        0x30..=0x39 | 0x41..=0x5a => char::from_u32(vk).map(|c| c.to_ascii_lowercase()),
+        0x60..=0x69 => char::from_digit(vk - 0x60, 10),
+        0x20 => Some(' '),
+        0xbd | 0x6d => Some('-'),
+        0xbe | 0x6e => Some('.'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALT: u32 = 0x38;
+    const SHIFT: u32 = 0x2a;
+    const TAB: u32 = 0x0f;
+    const BACKTICK: u32 = 0x29;
+    const ESC: u32 = 0x01;
+    const RIGHT: u32 = 0xe04d;
+    const CTRL: u32 = 0x1d;
+    const RCTRL: u32 = 0xe01d;
+    const RALT: u32 = 0xe038;
+
+    fn machine() -> HotkeyStateMachine {
+        let switch_windows =
+            Hotkey::create(SWITCH_WINDOWS_HOTKEY_ID, "switch windows", "alt+`").unwrap();
+        let switch_apps = Hotkey::create(SWITCH_APPS_HOTKEY_ID, "switch apps", "alt+tab").unwrap();
+        HotkeyStateMachine::new(&[&switch_windows, &switch_apps])
+    }
+
+    fn feed(machine: &mut HotkeyStateMachine, events: &[(u32, bool)]) -> Vec<KeyOutput> {
+        events
+            .iter()
+            .map(|(scan_code, is_down)| machine.handle(*scan_code, *is_down))
+            .collect()
+    }
+
+    fn pass() -> KeyOutput {
+        KeyOutput::default()
+    }
+
+    fn swallow(actions: &[KeyAction]) -> KeyOutput {
+        KeyOutput {
+            actions: actions.to_vec(),
+            swallow: true,
         }
     }
 
-    if let Some((id, reverse, is_cancel)) = send_action_message {
-        if id == SWITCH_APPS_HOTKEY_ID {
-            if is_cancel {
-                send_message_timeout(WINDOW, WM_USER_SWITCH_APPS_CANCEL, WPARAM(0), LPARAM(0));
-                IS_SWITCHING_APPS = false;
-            } else {
-                send_message_timeout(WINDOW, WM_USER_SWITCH_APPS, WPARAM(0), LPARAM(reverse));
-                IS_SWITCHING_APPS = true;
-            }
-            return LRESULT(1);
-        } else if id == SWITCH_WINDOWS_HOTKEY_ID {
-            send_message_timeout(WINDOW, WM_USER_SWITCH_WINDOWS, WPARAM(0), LPARAM(reverse));
-            IS_SWITCHING_APPS = false;
-            return LRESULT(1);
+    fn emit(actions: &[KeyAction]) -> KeyOutput {
+        KeyOutput {
+            actions: actions.to_vec(),
+            swallow: false,
         }
     }
-    CallNextHookEx(None, code, w_param, l_param)
+
+    #[test]
+    fn test_switch_apps() {
+        let mut machine = machine();
+        let outputs = feed(
+            &mut machine,
+            &[
+                (ALT, true),
+                (TAB, true),
+                (TAB, false),
+                (TAB, true),
+                (TAB, false),
+                (ALT, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+                swallow(&[]),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+                swallow(&[]),
+                emit(&[KeyAction::SwitchAppsDone]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switch_apps_reverse_and_arrows() {
+        let mut machine = machine();
+        let outputs = feed(
+            &mut machine,
+            &[
+                (ALT, true),
+                (RIGHT, true),
+                (SHIFT, true),
+                (TAB, true),
+                (TAB, false),
+                (SHIFT, false),
+                (RIGHT, true),
+                (RIGHT, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                // arrows are passed through until the switcher is shown
+                pass(),
+                pass(),
+                swallow(&[KeyAction::SwitchApps { reverse: true }]),
+                swallow(&[]),
+                pass(),
+                swallow(&[KeyAction::Navigate(Direction::Right)]),
+                swallow(&[]),
+            ]
+        );
+        let outputs = feed(&mut machine, &[(0xe048, true), (0x50, true), (TAB, true)]);
+        assert_eq!(
+            outputs,
+            [
+                swallow(&[KeyAction::Navigate(Direction::Up)]),
+                swallow(&[KeyAction::Navigate(Direction::Down)]),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_switch_apps() {
+        let mut machine = machine();
+        let outputs = feed(
+            &mut machine,
+            &[
+                (ALT, true),
+                (TAB, true),
+                (ESC, true),
+                (ESC, false),
+                (TAB, false),
+                (ALT, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+                swallow(&[KeyAction::SwitchAppsCancel]),
+                swallow(&[]),
+                swallow(&[]),
+                pass(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_switch_windows() {
+        let mut machine = machine();
+        let outputs = feed(
+            &mut machine,
+            &[
+                (ALT, true),
+                (BACKTICK, true),
+                (ALT, false),
+                (BACKTICK, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                swallow(&[KeyAction::SwitchWindows { reverse: false }]),
+                emit(&[KeyAction::SwitchWindowsDone]),
+                // the key-up must not leak even though the modifier was released first
+                swallow(&[]),
+            ]
+        );
+
+        machine.set_foreground_in_blacklist(true);
+        let outputs = feed(&mut machine, &[(ALT, true), (BACKTICK, true)]);
+        assert_eq!(outputs, [pass(), pass()]);
+    }
+
+    #[test]
+    fn test_switch_all_windows() {
+        const Q: u32 = 0x10;
+        let switch_all_windows =
+            Hotkey::create(SWITCH_ALL_WINDOWS_HOTKEY_ID, "switch all windows", "alt+q").unwrap();
+        let events = [
+            (ALT, true),
+            (Q, true),
+            (Q, false),
+            (RIGHT, true),
+            (RIGHT, false),
+            (ALT, false),
+        ];
+
+        let mut machine = HotkeyStateMachine::new(&[&switch_all_windows]);
+        let outputs = feed(&mut machine, &events);
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                swallow(&[KeyAction::SwitchAllWindows { reverse: false }]),
+                swallow(&[]),
+                pass(),
+                pass(),
+                emit(&[KeyAction::SwitchAllWindowsDone]),
+            ]
+        );
+
+        // with the overlay, arrows and Escape work as in the app switcher
+        let mut machine =
+            HotkeyStateMachine::new(&[&switch_all_windows]).with_all_windows_overlay(true);
+        let outputs = feed(&mut machine, &events);
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                swallow(&[KeyAction::SwitchAllWindows { reverse: false }]),
+                swallow(&[]),
+                swallow(&[KeyAction::Navigate(Direction::Right)]),
+                swallow(&[]),
+                emit(&[KeyAction::SwitchAllWindowsDone]),
+            ]
+        );
+        let outputs = feed(&mut machine, &[(ALT, true), (Q, true), (ESC, true)]);
+        assert_eq!(outputs[2], swallow(&[KeyAction::SwitchAppsCancel]));
+    }
+
+    #[test]
+    fn test_launchers() {
+        const WIN: u32 = 0xe05b;
+        const KEY_1: u32 = 0x02;
+        const KEY_2: u32 = 0x03;
+        let code = Hotkey::create(LAUNCHER_HOTKEY_ID, "launcher", "win+1").unwrap();
+        let notepad = Hotkey::create(LAUNCHER_HOTKEY_ID + 1, "launcher", "win+2").unwrap();
+        let mut machine = HotkeyStateMachine::new(&[&code, &notepad]);
+        let outputs = feed(
+            &mut machine,
+            &[
+                (WIN, true),
+                (KEY_1, true),
+                (KEY_1, false),
+                (KEY_1, true),
+                (KEY_1, false),
+                (KEY_2, true),
+                (KEY_2, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                swallow(&[KeyAction::Launch(0)]),
+                swallow(&[]),
+                swallow(&[KeyAction::Launch(0)]),
+                swallow(&[]),
+                swallow(&[KeyAction::Launch(1)]),
+                swallow(&[]),
+            ]
+        );
+        assert!(machine.is_win_pressed());
+        assert_eq!(
+            machine.handle(WIN, false),
+            emit(&[KeyAction::SwitchWindowsDone])
+        );
+        assert!(!machine.is_win_pressed());
+        // launchers are not subject to the blacklist of switching windows
+        machine.set_foreground_in_blacklist(true);
+        let outputs = feed(&mut machine, &[(WIN, true), (KEY_2, true)]);
+        assert_eq!(outputs[1], swallow(&[KeyAction::Launch(1)]));
+    }
+
+    #[test]
+    fn test_alt_printscreen() {
+        const SYSRQ: u32 = 0x54;
+        let hotkey = Hotkey::create(LAUNCHER_HOTKEY_ID, "launcher", "alt+printscreen").unwrap();
+        let mut machine = HotkeyStateMachine::new(&[&hotkey]);
+        // the hook reports PrintScreen as SysRq while Alt is held
+        let outputs = feed(&mut machine, &[(ALT, true), (SYSRQ, true), (SYSRQ, false)]);
+        assert_eq!(
+            outputs,
+            [pass(), swallow(&[KeyAction::Launch(0)]), swallow(&[])]
+        );
+        assert_eq!(
+            machine.handle(ALT, false),
+            emit(&[KeyAction::SwitchWindowsDone])
+        );
+    }
+
+    #[test]
+    fn test_pinned_switcher() {
+        const A: u32 = 0x1e;
+        const ENTER: u32 = 0x1c;
+        let mut machine = machine();
+        // keys pass through until the switcher is shown
+        assert_eq!(machine.handle(RIGHT, true), pass());
+        machine.set_switcher_pinned(true);
+        machine.set_typed_char(Some('a'));
+        let outputs = feed(&mut machine, &[(A, true), (A, false)]);
+        assert_eq!(
+            outputs,
+            [swallow(&[KeyAction::SearchInput('a')]), swallow(&[])]
+        );
+        machine.set_typed_char(None);
+        let outputs = feed(
+            &mut machine,
+            &[(RIGHT, true), (RIGHT, false), (ENTER, true), (ENTER, false)],
+        );
+        assert_eq!(
+            outputs,
+            [
+                swallow(&[KeyAction::Navigate(Direction::Right)]),
+                swallow(&[]),
+                swallow(&[KeyAction::SwitchAppsDone]),
+                swallow(&[]),
+            ]
+        );
+        assert_eq!(machine.handle(RIGHT, true), pass());
+
+        machine.set_switcher_pinned(true);
+        assert_eq!(
+            machine.handle(ESC, true),
+            swallow(&[KeyAction::SwitchAppsCancel])
+        );
+        assert_eq!(machine.handle(ENTER, true), pass());
+    }
+
+    #[test]
+    fn test_done_is_sent_once() {
+        let mut machine = machine();
+        let outputs = feed(
+            &mut machine,
+            &[
+                (ALT, true),
+                (TAB, true),
+                (TAB, false),
+                (ALT, false),
+                (ALT, true),
+                (ALT, false),
+            ],
+        );
+        assert_eq!(outputs[3], emit(&[KeyAction::SwitchAppsDone]));
+        assert_eq!(outputs[5], pass());
+    }
+
+    #[test]
+    fn test_held_keys_survive_reload() {
+        let mut previous = machine();
+        feed(&mut previous, &[(ALT, true), (TAB, true)]);
+        let mut machine = machine().with_held_keys(&previous);
+        let outputs = feed(
+            &mut machine,
+            &[(TAB, false), (TAB, true), (TAB, false), (ALT, false)],
+        );
+        assert_eq!(
+            outputs,
+            [
+                swallow(&[]),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+                swallow(&[]),
+                emit(&[KeyAction::SwitchAppsDone]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keys_without_modifier_pass_through() {
+        let mut machine = machine();
+        let outputs = feed(
+            &mut machine,
+            &[(TAB, true), (TAB, false), (ESC, true), (ESC, false)],
+        );
+        assert_eq!(outputs, [pass(), pass(), pass(), pass()]);
+    }
+
+    #[test]
+    fn test_multiple_modifiers() {
+        let switch_windows =
+            Hotkey::create(SWITCH_WINDOWS_HOTKEY_ID, "switch windows", "ctrl+alt+tab").unwrap();
+        let switch_apps = Hotkey::create(SWITCH_APPS_HOTKEY_ID, "switch apps", "alt+tab").unwrap();
+        let mut machine = HotkeyStateMachine::new(&[&switch_apps, &switch_windows]);
+        let outputs = feed(
+            &mut machine,
+            &[
+                (RALT, true),
+                (TAB, true),
+                (TAB, false),
+                (RCTRL, true),
+                (TAB, true),
+                (TAB, false),
+                (RCTRL, false),
+                (RALT, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+                swallow(&[]),
+                pass(),
+                swallow(&[KeyAction::SwitchWindows { reverse: false }]),
+                swallow(&[]),
+                emit(&[KeyAction::SwitchWindowsDone]),
+                pass(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_left_right_modifiers() {
+        let switch_apps =
+            Hotkey::create(SWITCH_APPS_HOTKEY_ID, "switch apps", "rctrl+space").unwrap();
+        let mut machine = HotkeyStateMachine::new(&[&switch_apps]);
+        let outputs = feed(
+            &mut machine,
+            &[
+                (CTRL, true),
+                (0x39, true),
+                (0x39, false),
+                (CTRL, false),
+                (RCTRL, true),
+                (0x39, true),
+                (0x39, false),
+                (RCTRL, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                pass(),
+                pass(),
+                pass(),
+                pass(),
+                pass(),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+                swallow(&[]),
+                emit(&[KeyAction::SwitchAppsDone]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shift_as_modifier_does_not_reverse() {
+        let switch_apps =
+            Hotkey::create(SWITCH_APPS_HOTKEY_ID, "switch apps", "shift+win+q").unwrap();
+        let mut machine = HotkeyStateMachine::new(&[&switch_apps]);
+        let outputs = feed(&mut machine, &[(0xe05b, true), (0x2a, true), (0x10, true)]);
+        assert_eq!(
+            outputs[2],
+            swallow(&[KeyAction::SwitchApps { reverse: false }])
+        );
+    }
+
+    #[test]
+    fn test_search_while_switching_apps() {
+        const A: u32 = 0x1e;
+        const BACKSPACE: u32 = 0x0e;
+        let mut machine = machine();
+        machine.set_typed_char(Some('a'));
+        assert_eq!(
+            feed(&mut machine, &[(A, true), (A, false)]),
+            [pass(), pass()]
+        );
+
+        machine.set_typed_char(None);
+        feed(&mut machine, &[(ALT, true), (TAB, true), (TAB, false)]);
+        machine.set_typed_char(Some('a'));
+        let outputs = feed(&mut machine, &[(A, true), (A, false)]);
+        assert_eq!(
+            outputs,
+            [swallow(&[KeyAction::SearchInput('a')]), swallow(&[])]
+        );
+        machine.set_typed_char(None);
+        let outputs = feed(
+            &mut machine,
+            &[
+                (BACKSPACE, true),
+                (BACKSPACE, false),
+                (TAB, true),
+                (ALT, false),
+            ],
+        );
+        assert_eq!(
+            outputs,
+            [
+                swallow(&[KeyAction::SearchBackspace]),
+                swallow(&[]),
+                swallow(&[KeyAction::SwitchApps { reverse: false }]),
+                emit(&[KeyAction::SwitchAppsDone]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_while_switcher_hidden() {
+        const A: u32 = 0x1e;
+        let mut machine = machine();
+        feed(&mut machine, &[(ALT, true), (TAB, true), (TAB, false)]);
+        machine.set_switcher_hidden(true);
+        machine.set_typed_char(Some('a'));
+        assert_eq!(
+            feed(&mut machine, &[(A, true), (A, false)]),
+            [pass(), pass()]
+        );
+        machine.set_switcher_hidden(false);
+        assert_eq!(
+            feed(&mut machine, &[(A, true), (A, false)]),
+            [swallow(&[KeyAction::SearchInput('a')]), swallow(&[])]
+        );
+    }
+
+    #[test]
+    fn test_action_keys_while_switching_apps() {
+        const Q: u32 = 0x10;
+        const DELETE: u32 = 0xe053;
+        let mut machine = machine().with_action_keys(&[
+            (Q, WindowAction::CloseApp),
+            (DELETE, WindowAction::CloseApp),
+        ]);
+        machine.set_typed_char(Some('q'));
+        assert_eq!(feed(&mut machine, &[(Q, true)]), [pass()]);
+
+        machine.set_typed_char(None);
+        feed(&mut machine, &[(ALT, true), (TAB, true), (TAB, false)]);
+        machine.set_typed_char(Some('q'));
+        let outputs = feed(&mut machine, &[(Q, true), (Q, false)]);
+        assert_eq!(
+            outputs,
+            [
+                swallow(&[KeyAction::Window(WindowAction::CloseApp)]),
+                swallow(&[])
+            ]
+        );
+        machine.set_typed_char(None);
+        let outputs = feed(&mut machine, &[(DELETE, true), (ALT, false)]);
+        assert_eq!(
+            outputs,
+            [
+                swallow(&[KeyAction::Window(WindowAction::CloseApp)]),
+                emit(&[KeyAction::SwitchAppsDone]),
+            ]
+        );
+    }
 }
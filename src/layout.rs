@@ -0,0 +1,471 @@
+use crate::win32::{POINT, RECT};
+
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const ICON_SIZE_BASE: i32 = 64;
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const WINDOW_BORDER_SIZE_BASE: i32 = 10;
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const ICON_BORDER_SIZE_BASE: i32 = 4;
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const FONT_SIZE_BASE: i32 = 16;
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const OUTLINE_WIDTH_BASE: i32 = 1;
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const MIN_ICON_SIZE_BASE: i32 = 48;
+/// The window is at least this many times the font size wide when it shows text.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const TEXT_WIDTH_FACTOR: i32 = 28;
+/// Icons go below the minimum size only when the grid would not fit on the monitor otherwise.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const ICON_SIZE_FLOOR: i32 = 16;
+
+/// The sizes of the switcher at 100% scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct LayoutStyle {
+    pub icon_size: i32,
+    /// Icons shrink to fit on one row down to this size, then wrap into more rows.
+    pub min_icon_size: i32,
+    /// The most icons in a row, 0 to fit as many as the monitor allows.
+    pub max_icons_per_row: usize,
+    pub window_border: i32,
+    pub icon_border: i32,
+    pub font_size: i32,
+    /// `None` rounds the corners by a quarter of the icon size.
+    pub corner_radius: Option<i32>,
+}
+
+impl Default for LayoutStyle {
+    fn default() -> Self {
+        Self {
+            icon_size: ICON_SIZE_BASE,
+            min_icon_size: MIN_ICON_SIZE_BASE,
+            max_icons_per_row: 0,
+            window_border: WINDOW_BORDER_SIZE_BASE,
+            icon_border: ICON_BORDER_SIZE_BASE,
+            font_size: FONT_SIZE_BASE,
+            corner_radius: None,
+        }
+    }
+}
+
+/// The geometry of the switcher in physical pixels, shared by painting and hit-testing.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct Layout {
+    /// The switcher window in screen coordinates, centered on the monitor.
+    pub window: RECT,
+    pub icon_size: i32,
+    pub icon_border: i32,
+    pub border_size: i32,
+    pub font_size: i32,
+    pub line_height: i32,
+    pub corner_radius: i32,
+    /// The width of the line around the window.
+    pub outline_width: i32,
+    pub columns: usize,
+    pub rows: usize,
+    /// The cell of every icon including its border, relative to the window.
+    pub items: Vec<RECT>,
+    /// The text lines below the icons, relative to the window.
+    pub lines: Vec<RECT>,
+}
+
+/// What is under a point of the switcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum Hit {
+    Item(usize),
+    Line(usize),
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl Layout {
+    pub fn new(
+        monitor: RECT,
+        dpi_scale: f64,
+        num_items: usize,
+        num_lines: usize,
+        style: &LayoutStyle,
+    ) -> Self {
+        let scale = |v: i32| (v as f64 * dpi_scale) as i32;
+        let monitor_width = (monitor.right - monitor.left).max(0);
+        let monitor_height = (monitor.bottom - monitor.top).max(0);
+
+        let border_size = scale(style.window_border);
+        let icon_border = scale(style.icon_border);
+        let font_size = scale(style.font_size);
+        let line_height = font_size * 2;
+        let icon_size_max = scale(style.icon_size).max(1);
+        let icon_size_min = scale(style.min_icon_size).clamp(1, icon_size_max);
+        let icon_size_floor = scale(ICON_SIZE_FLOOR).clamp(1, icon_size_min);
+
+        let num_lines = num_lines as i32;
+        let available_width = monitor_width - border_size * 2;
+        let available_height = monitor_height - border_size * 2 - line_height * num_lines;
+
+        // keep the room of one icon when the search matches nothing
+        let cells = num_items.clamp(1, i32::MAX as usize) as i32;
+        let max_columns = match style.max_icons_per_row {
+            0 => cells,
+            v => v.min(cells as usize) as i32,
+        };
+        let fit_columns = |icon_size: i32| {
+            (available_width / (icon_size + icon_border * 2)).clamp(1, max_columns)
+        };
+        let count_rows = |columns: i32| (cells + columns - 1) / columns;
+
+        let mut icon_size =
+            (available_width / max_columns - icon_border * 2).clamp(icon_size_min, icon_size_max);
+        let mut columns = fit_columns(icon_size);
+        // shrink below the minimum size rather than running off the monitor
+        while icon_size > icon_size_floor
+            && (icon_size + icon_border * 2) * count_rows(columns) > available_height
+        {
+            icon_size -= 1;
+            columns = fit_columns(icon_size);
+        }
+        let rows = count_rows(columns);
+
+        let item_size = icon_size + icon_border * 2;
+        let corner_radius = match style.corner_radius {
+            Some(v) => scale(v),
+            None => item_size / 4,
+        };
+        let text_width = if num_lines > 0 {
+            (font_size * TEXT_WIDTH_FACTOR).min(monitor_width)
+        } else {
+            0
+        };
+        let width = (item_size * columns + border_size * 2).max(text_width);
+        let height = item_size * rows + border_size * 2 + line_height * num_lines;
+        let left = monitor.left + (monitor_width - width) / 2;
+        let top = monitor.top + (monitor_height - height) / 2;
+
+        // the icons are centered when the text makes the window wider
+        let icons_left = (width - item_size * columns.min(num_items as i32)) / 2;
+        let items = (0..num_items)
+            .map(|i| {
+                let x = icons_left + item_size * (i as i32 % columns);
+                let y = border_size + item_size * (i as i32 / columns);
+                RECT {
+                    left: x,
+                    top: y,
+                    right: x + item_size,
+                    bottom: y + item_size,
+                }
+            })
+            .collect();
+        let lines_top = border_size + item_size * rows;
+        let lines = (0..num_lines)
+            .map(|i| RECT {
+                left: border_size,
+                top: lines_top + line_height * i,
+                right: width - border_size,
+                bottom: lines_top + line_height * (i + 1),
+            })
+            .collect();
+
+        Self {
+            window: RECT {
+                left,
+                top,
+                right: left + width,
+                bottom: top + height,
+            },
+            icon_size,
+            icon_border,
+            border_size,
+            font_size,
+            line_height,
+            corner_radius,
+            outline_width: scale(OUTLINE_WIDTH_BASE).max(1),
+            columns: columns as usize,
+            rows: rows as usize,
+            items,
+            lines,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.window.right - self.window.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.window.bottom - self.window.top
+    }
+
+    /// Moves the window to the given top-left corner, keeping it on the monitor if it fits.
+    pub fn move_to(&mut self, x: i32, y: i32, monitor: RECT) {
+        let (width, height) = (self.width(), self.height());
+        let left = x.min(monitor.right - width).max(monitor.left);
+        let top = y.min(monitor.bottom - height).max(monitor.top);
+        self.window = RECT {
+            left,
+            top,
+            right: left + width,
+            bottom: top + height,
+        };
+    }
+
+    /// The bounding box of all icons relative to the window, empty if there are none.
+    pub fn icons_rect(&self) -> RECT {
+        let mut rects = self.items.iter();
+        let Some(first) = rects.next() else {
+            return RECT::default();
+        };
+        rects.fold(*first, |acc, v| RECT {
+            left: acc.left.min(v.left),
+            top: acc.top.min(v.top),
+            right: acc.right.max(v.right),
+            bottom: acc.bottom.max(v.bottom),
+        })
+    }
+
+    /// The line drawn before the item at `index`, or at the end of the previous row if the
+    /// item starts a row, relative to the window. `None` before the first or after the last item.
+    pub fn separator(&self, index: usize) -> Option<RECT> {
+        let previous = self.items.get(index.checked_sub(1)?)?;
+        let item = self.items.get(index)?;
+        let width = self.outline_width;
+        let left = if item.top == previous.top {
+            item.left - width / 2
+        } else {
+            previous.right - width
+        };
+        Some(RECT {
+            left,
+            top: previous.top + self.icon_border,
+            right: left + width,
+            bottom: previous.bottom - self.icon_border,
+        })
+    }
+
+    /// Finds the icon or text line under a point in screen coordinates.
+    pub fn hit_test(&self, point: POINT) -> Option<Hit> {
+        let x = point.x - self.window.left;
+        let y = point.y - self.window.top;
+        if let Some(i) = self.items.iter().position(|v| contains(v, x, y)) {
+            return Some(Hit::Item(i));
+        }
+        self.lines
+            .iter()
+            .position(|v| contains(v, x, y))
+            .map(Hit::Line)
+    }
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn contains(rect: &RECT, x: i32, y: i32) -> bool {
+    x >= rect.left && x < rect.right && y >= rect.top && y < rect.bottom
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(width: i32, height: i32) -> RECT {
+        RECT {
+            left: 0,
+            top: 0,
+            right: width,
+            bottom: height,
+        }
+    }
+
+    fn item_size(layout: &Layout) -> i32 {
+        layout.icon_size + layout.icon_border * 2
+    }
+
+    fn center(layout: &Layout, rect: &RECT) -> POINT {
+        POINT {
+            x: layout.window.left + (rect.left + rect.right) / 2,
+            y: layout.window.top + (rect.top + rect.bottom) / 2,
+        }
+    }
+
+    /// Checks the invariants that painting and hit-testing rely on.
+    fn assert_consistent(layout: &Layout, monitor: &RECT) {
+        assert!(layout.window.left >= monitor.left && layout.window.right <= monitor.right);
+        assert!(layout.window.top >= monitor.top && layout.window.bottom <= monitor.bottom);
+        assert!(layout.rows * layout.columns >= layout.items.len());
+        let icons = layout.icons_rect();
+        for rect in layout.items.iter().chain(&layout.lines) {
+            assert!(rect.left >= layout.border_size && rect.top >= layout.border_size);
+            assert!(rect.right <= layout.width() - layout.border_size);
+            assert!(rect.bottom <= layout.height() - layout.border_size);
+        }
+        for rect in &layout.lines {
+            assert!(rect.top >= icons.bottom);
+        }
+        let step = (layout.items.len() / 100).max(1);
+        for (i, rect) in layout.items.iter().enumerate().step_by(step) {
+            assert_eq!(layout.hit_test(center(layout, rect)), Some(Hit::Item(i)));
+        }
+        for (i, rect) in layout.lines.iter().enumerate() {
+            assert_eq!(layout.hit_test(center(layout, rect)), Some(Hit::Line(i)));
+        }
+    }
+
+    #[test]
+    fn test_layout_dpi() {
+        let monitor = monitor(3840, 2160);
+        for dpi_scale in [1.0, 1.25, 1.5, 1.75, 2.0, 2.5, 3.0] {
+            let layout = Layout::new(monitor, dpi_scale, 10, 1, &LayoutStyle::default());
+            assert_eq!(layout.icon_size, (64.0 * dpi_scale) as i32);
+            assert_eq!(layout.corner_radius, item_size(&layout) / 4);
+            assert_eq!((layout.columns, layout.rows), (10, 1));
+            assert_eq!(
+                layout.width(),
+                item_size(&layout) * 10 + layout.border_size * 2
+            );
+            assert_consistent(&layout, &monitor);
+        }
+    }
+
+    #[test]
+    fn test_layout_small_monitor() {
+        let monitor = monitor(800, 600);
+        let layout = Layout::new(monitor, 1.0, 20, 2, &LayoutStyle::default());
+        assert_eq!(layout.icon_size, 48);
+        assert_eq!((layout.columns, layout.rows), (13, 2));
+        assert_consistent(&layout, &monitor);
+
+        // four rows of the minimum size are too tall, so the icons shrink a bit more
+        let layout = Layout::new(monitor, 2.0, 20, 2, &LayoutStyle::default());
+        assert_eq!(layout.icon_size, 92);
+        assert_eq!((layout.columns, layout.rows), (7, 3));
+        assert_consistent(&layout, &monitor);
+
+        // the text is never wider than the monitor
+        let monitor = self::monitor(300, 600);
+        let layout = Layout::new(monitor, 1.0, 1, 1, &LayoutStyle::default());
+        assert_eq!(layout.width(), 300);
+        assert_consistent(&layout, &monitor);
+    }
+
+    #[test]
+    fn test_layout_huge_count() {
+        let monitor = monitor(1920, 1080);
+        let layout = Layout::new(monitor, 1.0, 1000, 1, &LayoutStyle::default());
+        assert!(layout.icon_size < 48 && layout.icon_size >= ICON_SIZE_FLOOR);
+        assert_consistent(&layout, &monitor);
+
+        // past the floor the grid may overflow, but it stays well-formed
+        let layout = Layout::new(monitor, 1.0, 10000, 0, &LayoutStyle::default());
+        assert_eq!(layout.icon_size, ICON_SIZE_FLOOR);
+        assert_eq!(layout.columns, 1900 / 24);
+        assert_eq!(layout.rows, 10000usize.div_ceil(1900 / 24));
+        assert_eq!(layout.items.len(), 10000);
+    }
+
+    #[test]
+    fn test_layout_max_icons_per_row() {
+        let monitor = monitor(1920, 1080);
+        let style = LayoutStyle {
+            max_icons_per_row: 4,
+            ..Default::default()
+        };
+        let layout = Layout::new(monitor, 1.0, 10, 0, &style);
+        assert_eq!((layout.columns, layout.rows), (4, 3));
+        assert_eq!(layout.items[4].left, layout.items[0].left);
+        assert_eq!(layout.items[4].top, layout.items[0].bottom);
+        assert_consistent(&layout, &monitor);
+    }
+
+    #[test]
+    fn test_layout_style() {
+        let monitor = monitor(1920, 1080);
+        let style = LayoutStyle {
+            icon_size: 32,
+            corner_radius: Some(8),
+            ..Default::default()
+        };
+        // the minimum size never exceeds the icon size
+        let layout = Layout::new(monitor, 2.0, 5, 1, &style);
+        assert_eq!(layout.icon_size, 64);
+        assert_eq!(layout.corner_radius, 16);
+        assert_eq!(layout.outline_width, 2);
+        assert_consistent(&layout, &monitor);
+    }
+
+    #[test]
+    fn test_layout_no_items() {
+        let monitor = monitor(1920, 1080);
+        let layout = Layout::new(monitor, 1.0, 0, 1, &LayoutStyle::default());
+        assert!(layout.items.is_empty());
+        assert_eq!(layout.icons_rect(), RECT::default());
+        // the room of one icon is kept above the query
+        assert_eq!(layout.lines[0].top, layout.border_size + item_size(&layout));
+        assert_consistent(&layout, &monitor);
+    }
+
+    #[test]
+    fn test_separator() {
+        let monitor = monitor(1920, 1080);
+        let style = LayoutStyle {
+            max_icons_per_row: 3,
+            ..Default::default()
+        };
+        let layout = Layout::new(monitor, 2.0, 5, 0, &style);
+        assert_eq!(layout.separator(0), None);
+        assert_eq!(layout.separator(5), None);
+        let icons = layout.icons_rect();
+        let (a, b, c) = (layout.items[0], layout.items[1], layout.items[2]);
+        // between two icons of a row
+        assert_eq!(
+            layout.separator(1),
+            Some(RECT {
+                left: b.left - 1,
+                top: a.top + layout.icon_border,
+                right: b.left + 1,
+                bottom: a.bottom - layout.icon_border,
+            })
+        );
+        // at the end of the row when the next icon wraps
+        let rect = layout.separator(3).unwrap();
+        assert_eq!((rect.left, rect.right), (c.right - 2, c.right));
+        assert_eq!(rect.top, c.top + layout.icon_border);
+        assert!(rect.right <= icons.right);
+    }
+
+    #[test]
+    fn test_move_to() {
+        let monitor = monitor(1920, 1080);
+        let mut layout = Layout::new(monitor, 1.0, 3, 0, &LayoutStyle::default());
+        let (width, height) = (layout.width(), layout.height());
+        layout.move_to(100, 200, monitor);
+        assert_eq!((layout.window.left, layout.window.top), (100, 200));
+        assert_eq!((layout.width(), layout.height()), (width, height));
+        assert_consistent(&layout, &monitor);
+
+        layout.move_to(1900, -50, monitor);
+        assert_eq!(layout.window.right, 1920);
+        assert_eq!(layout.window.top, 0);
+        let item = layout.items[0];
+        assert_eq!(layout.hit_test(center(&layout, &item)), Some(Hit::Item(0)));
+    }
+
+    #[test]
+    fn test_hit_test() {
+        let monitor = RECT {
+            left: -1920,
+            top: 0,
+            right: 0,
+            bottom: 1080,
+        };
+        let layout = Layout::new(monitor, 1.0, 3, 1, &LayoutStyle::default());
+        assert_consistent(&layout, &monitor);
+        let corner = POINT {
+            x: layout.window.left,
+            y: layout.window.top,
+        };
+        assert_eq!(layout.hit_test(corner), None);
+        assert_eq!(layout.hit_test(POINT { x: 0, y: 0 }), None);
+        let border = POINT {
+            x: layout.window.left + layout.border_size - 1,
+            y: layout.window.top + layout.lines[0].top,
+        };
+        assert_eq!(layout.hit_test(border), None);
+    }
+}
@@ -1,16 +1,39 @@
 pub mod utils;
+pub mod win32;
+#[cfg(windows)]
 #[macro_use]
 pub mod macros;
 #[macro_use]
 extern crate log;
 
+#[cfg(windows)]
 mod app;
+mod cli;
 mod config;
+#[cfg(windows)]
+mod config_watcher;
+#[cfg(windows)]
 mod foreground;
+mod fuzzy;
+mod ipc;
+#[cfg(windows)]
+mod ipc_client;
+#[cfg(windows)]
+mod ipc_server;
 mod keyboard;
+mod layout;
+mod mru;
+#[cfg(windows)]
 mod painter;
+mod rules;
+#[cfg(windows)]
 mod startup;
+mod switcher;
+#[cfg(windows)]
 mod trayicon;
 
+#[cfg(windows)]
 pub use crate::app::start;
-pub use crate::config::{load_config, Config};
+#[cfg(windows)]
+pub use crate::cli::run as run_cli;
+pub use crate::config::{format_config_issues, load_config, Config, ConfigIssue};
@@ -1,28 +1,71 @@
 #![windows_subsystem = "windows"]
 
+#[cfg(windows)]
 use anyhow::{anyhow, bail, Result};
+#[cfg(windows)]
 use std::{
     fs::{File, OpenOptions},
     path::Path,
 };
 
-use window_switcher::{alert, load_config, start, utils::SingleInstance};
+#[cfg(windows)]
+use window_switcher::{
+    alert, format_config_issues, load_config, run_cli, start, utils::SingleInstance, Config,
+};
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("Window-Switcher only runs on Windows");
+    std::process::exit(1);
+}
 
+#[cfg(windows)]
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        unsafe {
+            use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+            // there is no console of our own as this is a GUI app, so print to the shell's
+            let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+        if let Err(err) = run_cli(&args) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
     if let Err(err) = run() {
         alert!("{err}");
         std::process::exit(1);
     }
 }
 
+#[cfg(windows)]
 fn run() -> Result<()> {
     unsafe {
-        let _ = windows::Win32::UI::HiDpi::SetProcessDpiAwarenessContext(
-            windows::Win32::UI::HiDpi::DPI_AWARENESS_CONTEXT_SYSTEM_AWARE,
-        );
+        use windows::Win32::UI::HiDpi::{
+            SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+            DPI_AWARENESS_CONTEXT_SYSTEM_AWARE,
+        };
+        // per-monitor awareness keeps the switcher sharp on mixed-DPI setups,
+        // it is not available before Windows 10 1703
+        if SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2).is_err() {
+            let _ = SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_SYSTEM_AWARE);
+        }
+    }
+    unsafe {
+        use windows::Win32::System::Com::{CoInitializeEx, COINIT_APARTMENTTHREADED};
+        // the shell needs COM to look up the icons of apps by their AppUserModelID
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
     }
 
-    let config = load_config().unwrap_or_default();
+    let (config, issues) = match load_config() {
+        Ok(v) => v,
+        Err(err) => {
+            alert!("{err}\n\nThe default config is used.");
+            (Config::default(), vec![])
+        }
+    };
     if let Some(log_file) = &config.log_file {
         let file = prepare_log_file(log_file).map_err(|err| {
             anyhow!(
@@ -32,6 +75,10 @@ fn run() -> Result<()> {
         })?;
         simple_logging::log_to(file, config.log_level);
     }
+    if !issues.is_empty() {
+        let issues = format_config_issues(&issues);
+        alert!("Found problems in the config file, the affected settings use their defaults.\n\n{issues}");
+    }
     let instance = SingleInstance::create("WindowSwitcherMutex")?;
     if !instance.is_single() {
         bail!("Another instance is running. This instance will abort.")
@@ -39,6 +86,7 @@ fn run() -> Result<()> {
     start(&config)
 }
 
+#[cfg(windows)]
 fn prepare_log_file(path: &Path) -> std::io::Result<File> {
     if path.exists() {
         OpenOptions::new().append(true).open(path)
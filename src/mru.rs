@@ -0,0 +1,102 @@
+use crate::win32::HWND;
+use indexmap::IndexMap;
+
+/// Old entries are dropped once the history grows beyond this size.
+#[cfg_attr(not(windows), allow(dead_code))]
+const MAX_HISTORY: usize = 256;
+
+/// Windows in the order they were last activated, the most recent one first.
+///
+/// Unlike the Z-order, it is not affected by topmost windows, owned popups or
+/// windows that activate themselves, so the switcher order stays predictable.
+#[derive(Debug, Default)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct MruHistory {
+    hwnds: Vec<isize>,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl MruHistory {
+    /// Records that the window was activated.
+    pub fn touch(&mut self, hwnd: HWND) {
+        let id = hwnd.0 as isize;
+        if id == 0 {
+            return;
+        }
+        self.hwnds.retain(|v| *v != id);
+        self.hwnds.insert(0, id);
+        self.hwnds.truncate(MAX_HISTORY);
+    }
+
+    /// The position of the window in the history, windows never seen come last.
+    pub fn rank(&self, hwnd: HWND) -> usize {
+        let id = hwnd.0 as isize;
+        self.hwnds
+            .iter()
+            .position(|v| *v == id)
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Orders the windows of every app, then the apps by their most recent window.
+    /// The sort is stable, so windows missing from the history keep their Z-order.
+    pub fn sort<T>(&self, windows: &mut IndexMap<String, Vec<(HWND, T)>>) {
+        for hwnds in windows.values_mut() {
+            hwnds.sort_by_key(|(hwnd, _)| self.rank(*hwnd));
+        }
+        windows.sort_by_cached_key(|_, hwnds| {
+            hwnds
+                .first()
+                .map(|(hwnd, _)| self.rank(*hwnd))
+                .unwrap_or(usize::MAX)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hwnd(id: isize) -> HWND {
+        HWND(id as _)
+    }
+
+    #[test]
+    fn test_touch() {
+        let mut history = MruHistory::default();
+        history.touch(hwnd(1));
+        history.touch(hwnd(2));
+        history.touch(hwnd(1));
+        history.touch(hwnd(0));
+        assert_eq!(history.rank(hwnd(1)), 0);
+        assert_eq!(history.rank(hwnd(2)), 1);
+        assert_eq!(history.rank(hwnd(3)), usize::MAX);
+
+        for id in 0..MAX_HISTORY as isize {
+            history.touch(hwnd(id + 10));
+        }
+        assert_eq!(history.rank(hwnd(1)), usize::MAX);
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut history = MruHistory::default();
+        history.touch(hwnd(4));
+        history.touch(hwnd(2));
+        history.touch(hwnd(3));
+
+        let mut windows: IndexMap<String, Vec<(HWND, ())>> = IndexMap::new();
+        windows.insert("a".into(), vec![(hwnd(1), ()), (hwnd(2), ())]);
+        windows.insert("b".into(), vec![(hwnd(5), ()), (hwnd(6), ())]);
+        windows.insert("c".into(), vec![(hwnd(4), ()), (hwnd(3), ())]);
+        history.sort(&mut windows);
+
+        let order: Vec<(&str, Vec<isize>)> = windows
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.iter().map(|(h, _)| h.0 as isize).collect()))
+            .collect();
+        assert_eq!(
+            order,
+            [("c", vec![3, 4]), ("a", vec![2, 1]), ("b", vec![5, 6])]
+        );
+    }
+}
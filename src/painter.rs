@@ -1,7 +1,13 @@
-use crate::app::SwitchAppsState;
-use crate::utils::{check_error, get_moinitor_rect, is_light_theme, is_win11};
+use crate::config::{Config, Position, Theme, ThemeColors};
+use crate::layout::{Hit, Layout, LayoutStyle};
+use crate::switcher::SwitchAppsState;
+use crate::utils::{
+    check_error, get_cursor_monitor, get_monitor_dpi_scale, get_monitor_rect, get_point_monitor,
+    get_primary_monitor, get_window_monitor, is_light_theme, is_win11, to_wstring,
+};
 
 use anyhow::{Context, Result};
+use windows::core::PCWSTR;
 use windows::Win32::{
     Foundation::{COLORREF, HWND, POINT, RECT, SIZE},
     Graphics::{
@@ -9,37 +15,40 @@ use windows::Win32::{
             CreateCompatibleBitmap, CreateCompatibleDC, CreateRoundRectRgn, CreateSolidBrush,
             DeleteDC, DeleteObject, FillRect, FillRgn, GetDC, ReleaseDC, SelectObject,
             SetStretchBltMode, StretchBlt, AC_SRC_ALPHA, AC_SRC_OVER, BLENDFUNCTION, HALFTONE,
-            HBITMAP, HDC, HPALETTE, SRCCOPY,
+            HBITMAP, HBRUSH, HDC, HMONITOR, HPALETTE, SRCCOPY,
         },
         GdiPlus::{
-            FillModeAlternate, GdipAddPathArc, GdipClosePathFigure, GdipCreateBitmapFromHBITMAP,
-            GdipCreateFromHDC, GdipCreatePath, GdipCreatePen1, GdipDeleteBrush, GdipDeleteGraphics,
-            GdipDeletePath, GdipDeletePen, GdipDisposeImage, GdipDrawImageRect, GdipFillPath,
-            GdipFillRectangle, GdipGetPenBrushFill, GdipSetInterpolationMode, GdipSetSmoothingMode,
-            GdiplusShutdown, GdiplusStartup, GdiplusStartupInput, GpBitmap, GpBrush, GpGraphics,
-            GpImage, GpPath, GpPen, InterpolationModeHighQualityBicubic, SmoothingModeAntiAlias,
-            Unit,
+            ColorAdjustTypeDefault, ColorMatrix, ColorMatrixFlagsDefault, FillModeAlternate,
+            FontStyleRegular, GdipAddPathArc, GdipAddPathRectangle, GdipClosePathFigure,
+            GdipCreateBitmapFromHBITMAP, GdipCreateFont, GdipCreateFontFamilyFromName,
+            GdipCreateFromHDC, GdipCreateImageAttributes, GdipCreatePath, GdipCreatePen1,
+            GdipCreateSolidFill, GdipCreateStringFormat, GdipDeleteBrush, GdipDeleteFont,
+            GdipDeleteFontFamily, GdipDeleteGraphics, GdipDeletePath, GdipDeletePen,
+            GdipDeleteStringFormat, GdipDisposeImage, GdipDisposeImageAttributes,
+            GdipDrawImageRectRect, GdipDrawPath, GdipDrawString, GdipFillPath,
+            GdipSetImageAttributesColorMatrix, GdipSetInterpolationMode, GdipSetSmoothingMode,
+            GdipSetStringFormatAlign, GdipSetStringFormatFlags, GdipSetStringFormatLineAlign,
+            GdipSetStringFormatTrimming, GdipSetTextRenderingHint, GdiplusShutdown, GdiplusStartup,
+            GdiplusStartupInput, GpBitmap, GpBrush, GpFont, GpFontFamily, GpGraphics, GpImage,
+            GpImageAttributes, GpPath, GpPen, GpSolidFill, GpStringFormat,
+            InterpolationModeHighQualityBicubic, RectF, SmoothingModeAntiAlias, StringAlignment,
+            StringAlignmentCenter, StringAlignmentNear, StringFormatFlagsNoWrap,
+            StringTrimmingEllipsisCharacter, TextRenderingHintAntiAliasGridFit, UnitPixel,
         },
     },
     UI::{
-        HiDpi::GetDpiForWindow,
         Input::KeyboardAndMouse::SetFocus,
         WindowsAndMessaging::{
-            DrawIconEx, GetCursorPos, ShowWindow, UpdateLayeredWindow, DI_NORMAL, SW_HIDE, SW_SHOW,
-            ULW_ALPHA,
+            DrawIconEx, GetCursorPos, GetForegroundWindow, ShowWindow, UpdateLayeredWindow,
+            DI_NORMAL, HICON, SW_HIDE, SW_SHOW, ULW_ALPHA,
         },
     },
 };
 
-pub const BG_DARK_COLOR: u32 = 0x4c4c4c;
-pub const FG_DARK_COLOR: u32 = 0x3b3b3b;
-pub const BG_LIGHT_COLOR: u32 = 0xe0e0e0;
-pub const FG_LIGHT_COLOR: u32 = 0xf2f2f2;
 pub const ALPHA_MASK: u32 = 0xff000000;
-pub const ICON_SIZE_BASE: i32 = 64;
-pub const WINDOW_BORDER_SIZE_BASE: i32 = 10;
-pub const ICON_BORDER_SIZE_BASE: i32 = 4;
 pub const SCALE_FACTOR: i32 = 6;
+pub const MAX_WINDOW_LIST_ROWS: usize = 10;
+pub const FONT_FAMILY: &str = "Segoe UI";
 
 // GDI Antialiasing Painter
 pub struct GdiAAPainter {
@@ -47,11 +56,16 @@ pub struct GdiAAPainter {
     hwnd: HWND,
     hdc_screen: HDC,
     rounded_corner: bool,
+    layout_style: LayoutStyle,
+    theme: Theme,
+    position: Position,
+    /// The monitor the switcher is on while it is shown.
+    monitor: Option<HMONITOR>,
     show: bool,
 }
 
 impl GdiAAPainter {
-    pub fn new(hwnd: HWND) -> Result<Self> {
+    pub fn new(hwnd: HWND, config: &Config) -> Result<Self> {
         let startup_input = GdiplusStartupInput {
             GdiplusVersion: 1,
             ..Default::default()
@@ -68,40 +82,37 @@ impl GdiAAPainter {
             hwnd,
             hdc_screen,
             rounded_corner,
+            layout_style: layout_style(config, rounded_corner),
+            theme: config.theme,
+            position: config.switch_apps_position,
+            monitor: None,
             show: false,
         })
     }
 
-    pub fn paint(&mut self, state: &SwitchAppsState) {
-        let dpi_scale = get_dpi_scale(self.hwnd);
-        let icon_size_max = (ICON_SIZE_BASE as f64 * dpi_scale) as i32;
-        let border_size = (WINDOW_BORDER_SIZE_BASE as f64 * dpi_scale) as i32;
-        let icon_border = (ICON_BORDER_SIZE_BASE as f64 * dpi_scale) as i32;
-
-        let Coordinate {
-            x,
-            y,
-            width,
-            height,
-            icon_size,
-            item_size,
-        } = Coordinate::new(
-            state.apps.len() as i32,
-            icon_size_max,
-            border_size,
-            icon_border,
-        );
+    pub fn set_config(&mut self, config: &Config) {
+        self.layout_style = layout_style(config, self.rounded_corner);
+        self.theme = config.theme;
+        self.position = config.switch_apps_position;
+    }
 
-        let corner_radius = if self.rounded_corner {
-            item_size / 4
-        } else {
-            0
-        };
+    /// Paints the switcher and returns the number of icons in a row.
+    pub fn paint(&mut self, state: &SwitchAppsState) -> usize {
+        if !self.show {
+            // stay on the same monitor until the switcher is closed
+            self.monitor = Some(self.find_monitor());
+        }
+        let lines = text_lines(state);
+        let layout = self.layout(state, lines.len());
+        let (width, height) = (layout.width(), layout.height());
+        let corner_radius = layout.corner_radius;
 
         let hwnd = self.hwnd;
         let hdc_screen = self.hdc_screen;
 
-        let (fg_color, bg_color) = theme_color(is_light_theme());
+        let light_theme = is_light_theme();
+        let colors = *self.theme.colors(light_theme);
+        let text_color = if light_theme { 0x000000 } else { 0xffffff };
 
         unsafe {
             let hdc_mem = CreateCompatibleDC(Some(hdc_screen));
@@ -114,73 +125,157 @@ impl GdiAAPainter {
             GdipSetSmoothingMode(graphics_ptr, SmoothingModeAntiAlias);
             GdipSetInterpolationMode(graphics_ptr, InterpolationModeHighQualityBicubic);
 
-            let mut bg_pen = GpPen::default();
-            let mut bg_pen_ptr: *mut GpPen = &mut bg_pen;
-            GdipCreatePen1(ALPHA_MASK | bg_color, 0.0, Unit(0), &mut bg_pen_ptr as _);
-
-            let mut bg_brush = GpBrush::default();
-            let mut bg_brush_ptr: *mut GpBrush = &mut bg_brush;
-            GdipGetPenBrushFill(bg_pen_ptr, &mut bg_brush_ptr as _);
-
-            if self.rounded_corner {
-                draw_round_rect(
-                    graphics_ptr,
-                    bg_brush_ptr,
-                    0.0,
-                    0.0,
-                    width as f32,
-                    height as f32,
+            let mut bg_brush_ptr: *mut GpSolidFill = std::ptr::null_mut();
+            GdipCreateSolidFill(colors.background, &mut bg_brush_ptr as _);
+            draw_round_rect(
+                graphics_ptr,
+                bg_brush_ptr as *mut GpBrush,
+                0.0,
+                0.0,
+                width as f32,
+                height as f32,
+                corner_radius as f32,
+            );
+            GdipDeleteBrush(bg_brush_ptr as *mut GpBrush);
+
+            if colors.border & ALPHA_MASK != 0 {
+                // keep the whole line inside the window
+                let inset = layout.outline_width as f32 / 2.0;
+                let mut pen_ptr: *mut GpPen = std::ptr::null_mut();
+                GdipCreatePen1(
+                    colors.border,
+                    layout.outline_width as f32,
+                    UnitPixel,
+                    &mut pen_ptr as _,
+                );
+                let path_ptr = round_rect_path(
+                    inset,
+                    inset,
+                    width as f32 - inset,
+                    height as f32 - inset,
                     corner_radius as f32,
                 );
+                GdipDrawPath(graphics_ptr, pen_ptr, path_ptr);
+                GdipDeletePath(path_ptr);
+                GdipDeletePen(pen_ptr);
+            }
+
+            let icons = layout.icons_rect();
+            // a faint line of the text color between the pinned apps and the others
+            let separator = blend_color(0x60000000 | text_color, ALPHA_MASK | colors.background);
+            let bitmap_icons = if layout.items.is_empty() {
+                None
             } else {
-                GdipFillRectangle(
+                draw_icons(state, &layout, hdc_screen, &colors, separator)
+            };
+            if let Some(bitmap_icons) = bitmap_icons {
+                let mut bitmap = GpBitmap::default();
+                let mut bitmap_ptr: *mut GpBitmap = &mut bitmap as _;
+                GdipCreateBitmapFromHBITMAP(
+                    bitmap_icons,
+                    HPALETTE::default(),
+                    &mut bitmap_ptr as _,
+                );
+
+                // the bitmap is opaque, fade it like the background around it
+                let mut attributes_ptr: *mut GpImageAttributes = std::ptr::null_mut();
+                GdipCreateImageAttributes(&mut attributes_ptr as _);
+                let mut matrix = ColorMatrix::default();
+                for i in 0..5 {
+                    matrix.m[i * 6] = 1.0;
+                }
+                matrix.m[18] = (colors.background >> 24) as f32 / 255.0;
+                GdipSetImageAttributesColorMatrix(
+                    attributes_ptr,
+                    ColorAdjustTypeDefault,
+                    true,
+                    &matrix,
+                    std::ptr::null(),
+                    ColorMatrixFlagsDefault,
+                );
+
+                let image_ptr: *mut GpImage = bitmap_ptr as *mut GpImage;
+                let icons_width = (icons.right - icons.left) as f32;
+                let icons_height = (icons.bottom - icons.top) as f32;
+                GdipDrawImageRectRect(
                     graphics_ptr,
-                    bg_brush_ptr,
+                    image_ptr,
+                    icons.left as f32,
+                    icons.top as f32,
+                    icons_width,
+                    icons_height,
                     0.0,
                     0.0,
-                    width as f32,
-                    height as f32,
+                    icons_width,
+                    icons_height,
+                    UnitPixel,
+                    attributes_ptr,
+                    0,
+                    std::ptr::null_mut(),
                 );
+                GdipDisposeImageAttributes(attributes_ptr);
+                GdipDisposeImage(image_ptr);
+                let _ = DeleteObject(bitmap_icons.into());
             }
 
-            let icons_width = item_size * state.apps.len() as i32;
-            let icons_height = item_size;
-            let bitmap_icons = draw_icons(
-                state,
-                hdc_screen,
-                icon_size,
-                icon_border,
-                icons_width,
-                icons_height,
-                corner_radius,
-                fg_color,
-                bg_color,
-            );
-
-            let mut bitmap = GpBitmap::default();
-            let mut bitmap_ptr: *mut GpBitmap = &mut bitmap as _;
-            GdipCreateBitmapFromHBITMAP(bitmap_icons, HPALETTE::default(), &mut bitmap_ptr as _);
-
-            let image_ptr: *mut GpImage = bitmap_ptr as *mut GpImage;
-            GdipDrawImageRect(
-                graphics_ptr,
-                image_ptr,
-                border_size as f32,
-                border_size as f32,
-                icons_width as f32,
-                icons_height as f32,
-            );
+            let mut fg_brush_ptr: *mut GpSolidFill = std::ptr::null_mut();
+            GdipCreateSolidFill(colors.selection, &mut fg_brush_ptr as _);
+            for (line, rect) in lines.iter().zip(&layout.lines) {
+                let rect = RectF {
+                    X: rect.left as f32,
+                    Y: rect.top as f32,
+                    Width: (rect.right - rect.left) as f32,
+                    Height: (rect.bottom - rect.top) as f32,
+                };
+                let (text, align) = match line {
+                    TextLine::Title(text) | TextLine::Query(text) => (*text, StringAlignmentCenter),
+                    TextLine::Window {
+                        title, selected, ..
+                    } => {
+                        if *selected {
+                            draw_round_rect(
+                                graphics_ptr,
+                                fg_brush_ptr as *mut GpBrush,
+                                rect.X,
+                                rect.Y,
+                                rect.X + rect.Width,
+                                rect.Y + rect.Height,
+                                corner_radius.min(layout.line_height) as f32,
+                            );
+                        }
+                        (*title, StringAlignmentNear)
+                    }
+                };
+                let padding = layout.icon_border as f32 * 2.0;
+                let rect = RectF {
+                    X: rect.X + padding,
+                    Width: rect.Width - padding * 2.0,
+                    ..rect
+                };
+                draw_text(
+                    graphics_ptr,
+                    text,
+                    rect,
+                    align,
+                    layout.font_size as f32,
+                    text_color,
+                );
+            }
+            GdipDeleteBrush(fg_brush_ptr as *mut GpBrush);
 
             let blend = BLENDFUNCTION {
                 BlendOp: AC_SRC_OVER as _,
-                SourceConstantAlpha: 255,
+                SourceConstantAlpha: (self.theme.opacity as u32 * 255 / 100) as u8,
                 AlphaFormat: AC_SRC_ALPHA as _,
                 ..Default::default()
             };
             let _ = UpdateLayeredWindow(
                 hwnd,
                 Some(hdc_screen),
-                Some(&POINT { x, y }),
+                Some(&POINT {
+                    x: layout.window.left,
+                    y: layout.window.top,
+                }),
                 Some(&SIZE {
                     cx: width,
                     cy: height,
@@ -192,24 +287,20 @@ impl GdiAAPainter {
                 ULW_ALPHA,
             );
 
-            GdipDisposeImage(image_ptr);
-            GdipDeleteBrush(bg_brush_ptr);
-            GdipDeletePen(bg_pen_ptr);
             GdipDeleteGraphics(graphics_ptr);
 
-            let _ = DeleteObject(bitmap_icons.into());
             let _ = DeleteObject(bitmap_mem.into());
             let _ = DeleteDC(hdc_mem);
         }
 
-        if self.show {
-            return;
-        }
-        unsafe {
-            let _ = ShowWindow(self.hwnd, SW_SHOW);
-            let _ = SetFocus(Some(self.hwnd));
+        if !self.show {
+            unsafe {
+                let _ = ShowWindow(self.hwnd, SW_SHOW);
+                let _ = SetFocus(Some(self.hwnd));
+            }
+            self.show = true;
         }
-        self.show = true;
+        layout.columns
     }
 
     pub fn unpaint(&mut self, _state: SwitchAppsState) {
@@ -217,43 +308,62 @@ impl GdiAAPainter {
             let _ = ShowWindow(self.hwnd, SW_HIDE);
         }
         self.show = false;
+        self.monitor = None;
     }
 
-    pub fn find_clicked_app_index(&self, state: &SwitchAppsState) -> Option<usize> {
+    pub fn find_clicked_item(&self, state: &SwitchAppsState) -> Option<ClickedItem> {
         let cursor_pos = unsafe {
             let mut pos = POINT::default();
             let _ = GetCursorPos(&mut pos);
             pos
         };
 
-        let dpi_scale = get_dpi_scale(self.hwnd);
-        let icon_size_max = (ICON_SIZE_BASE as f64 * dpi_scale) as i32;
-        let border_size = (WINDOW_BORDER_SIZE_BASE as f64 * dpi_scale) as i32;
-        let icon_border = (ICON_BORDER_SIZE_BASE as f64 * dpi_scale) as i32;
-
-        let Coordinate {
-            x, y, item_size, ..
-        } = Coordinate::new(
-            state.apps.len() as i32,
-            icon_size_max,
-            border_size,
-            icon_border,
-        );
+        let lines = text_lines(state);
+        match self.layout(state, lines.len()).hit_test(cursor_pos)? {
+            Hit::Item(i) => Some(ClickedItem::App(i)),
+            Hit::Line(i) => match lines.get(i)? {
+                TextLine::Window { index, .. } => Some(ClickedItem::Window(*index)),
+                _ => None,
+            },
+        }
+    }
 
-        let xpos = cursor_pos.x - x;
-        let ypos = cursor_pos.y - y;
+    fn layout(&self, state: &SwitchAppsState, num_lines: usize) -> Layout {
+        let monitor = self.monitor.unwrap_or_else(|| self.find_monitor());
+        let monitor_rect = get_monitor_rect(monitor);
+        let mut layout = Layout::new(
+            monitor_rect,
+            get_monitor_dpi_scale(monitor),
+            state.apps.len(),
+            num_lines,
+            &self.layout_style,
+        );
+        if let Position::Fixed(x, y) = self.position {
+            layout.move_to(x, y, monitor_rect);
+        }
+        layout
+    }
 
-        let cy = border_size;
-        for (i, _) in state.apps.iter().enumerate() {
-            let cx = border_size + item_size * (i as i32);
-            if xpos >= cx && xpos < cx + item_size && ypos >= cy && ypos < cy + item_size {
-                return Some(i);
-            }
+    fn find_monitor(&self) -> HMONITOR {
+        match self.position {
+            Position::Primary => get_primary_monitor(),
+            Position::Cursor => get_cursor_monitor(),
+            Position::Foreground => match unsafe { GetForegroundWindow() } {
+                hwnd if hwnd.is_invalid() => get_cursor_monitor(),
+                hwnd => get_window_monitor(hwnd),
+            },
+            Position::Fixed(x, y) => get_point_monitor(x, y),
         }
-        None
     }
 }
 
+/// What was clicked in the switcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickedItem {
+    App(usize),
+    Window(usize),
+}
+
 impl Drop for GdiAAPainter {
     fn drop(&mut self) {
         unsafe {
@@ -263,11 +373,19 @@ impl Drop for GdiAAPainter {
     }
 }
 
-const fn theme_color(light_theme: bool) -> (u32, u32) {
-    match light_theme {
-        true => (FG_LIGHT_COLOR, BG_LIGHT_COLOR),
-        false => (FG_DARK_COLOR, BG_DARK_COLOR),
-    }
+/// GDI takes colors as `0x00BBGGRR`, unlike the `0xAARRGGBB` of GDI+.
+const fn to_colorref(color: u32) -> COLORREF {
+    COLORREF(((color & 0xff) << 16) | (color & 0xff00) | ((color >> 16) & 0xff))
+}
+
+/// Blends a translucent color over the background, the result is opaque.
+fn blend_color(color: u32, background: u32) -> u32 {
+    let alpha = color >> 24;
+    let channel = |shift: u32| {
+        let (fg, bg) = ((color >> shift) & 0xff, (background >> shift) & 0xff);
+        ((fg * alpha + bg * (255 - alpha)) / 255) << shift
+    };
+    ALPHA_MASK | channel(16) | channel(8) | channel(0)
 }
 
 unsafe fn draw_round_rect(
@@ -280,9 +398,27 @@ unsafe fn draw_round_rect(
     corner_radius: f32,
 ) {
     unsafe {
-        let mut path = GpPath::default();
-        let mut path_ptr: *mut GpPath = &mut path;
+        let path_ptr = round_rect_path(left, top, right, bottom, corner_radius);
+        GdipFillPath(graphic_ptr, brush_ptr, path_ptr);
+        GdipDeletePath(path_ptr);
+    }
+}
+
+/// Creates the path of a rectangle with rounded corners, the caller deletes it.
+unsafe fn round_rect_path(
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+    corner_radius: f32,
+) -> *mut GpPath {
+    unsafe {
+        let mut path_ptr: *mut GpPath = std::ptr::null_mut();
         GdipCreatePath(FillModeAlternate, &mut path_ptr as _);
+        if corner_radius <= 0.0 {
+            GdipAddPathRectangle(path_ptr, left, top, right - left, bottom - top);
+            return path_ptr;
+        }
         GdipAddPathArc(
             path_ptr,
             left,
@@ -320,151 +456,286 @@ unsafe fn draw_round_rect(
             90.0,
         );
         GdipClosePathFigure(path_ptr);
-        GdipFillPath(graphic_ptr, brush_ptr, path_ptr);
-        GdipDeletePath(path_ptr);
+        path_ptr
+    }
+}
+
+/// A line of text under the icons.
+enum TextLine<'a> {
+    Title(&'a str),
+    Window {
+        title: &'a str,
+        index: usize,
+        selected: bool,
+    },
+    Query(&'a str),
+}
+
+/// The title of the selection or the expanded window list, followed by the search query.
+fn text_lines(state: &SwitchAppsState) -> Vec<TextLine<'_>> {
+    let mut lines = vec![];
+    match &state.window_list {
+        Some(list) => {
+            // scroll to keep the selected window visible
+            let start = (list.index + 1).saturating_sub(MAX_WINDOW_LIST_ROWS);
+            for (index, (_, title)) in list
+                .windows
+                .iter()
+                .enumerate()
+                .skip(start)
+                .take(MAX_WINDOW_LIST_ROWS)
+            {
+                lines.push(TextLine::Window {
+                    title,
+                    index,
+                    selected: index == list.index,
+                });
+            }
+        }
+        None if state.show_title => {
+            if let Some(title) = state.selected_title() {
+                lines.push(TextLine::Title(title));
+            }
+        }
+        None => {}
+    }
+    if !state.query.is_empty() {
+        lines.push(TextLine::Query(&state.query));
     }
+    lines
 }
 
-#[allow(clippy::too_many_arguments)]
+unsafe fn draw_text(
+    graphics_ptr: *mut GpGraphics,
+    text: &str,
+    rect: RectF,
+    align: StringAlignment,
+    font_size: f32,
+    color: u32,
+) {
+    unsafe {
+        let family_name = to_wstring(FONT_FAMILY);
+        let mut family_ptr: *mut GpFontFamily = std::ptr::null_mut();
+        GdipCreateFontFamilyFromName(
+            PCWSTR(family_name.as_ptr()),
+            std::ptr::null_mut(),
+            &mut family_ptr as _,
+        );
+        let mut font_ptr: *mut GpFont = std::ptr::null_mut();
+        GdipCreateFont(
+            family_ptr,
+            font_size,
+            FontStyleRegular.0,
+            UnitPixel,
+            &mut font_ptr as _,
+        );
+        let mut brush_ptr: *mut GpSolidFill = std::ptr::null_mut();
+        GdipCreateSolidFill(ALPHA_MASK | color, &mut brush_ptr as _);
+        let mut format_ptr: *mut GpStringFormat = std::ptr::null_mut();
+        GdipCreateStringFormat(0, 0, &mut format_ptr as _);
+        GdipSetStringFormatAlign(format_ptr, align);
+        GdipSetStringFormatLineAlign(format_ptr, StringAlignmentCenter);
+        GdipSetStringFormatTrimming(format_ptr, StringTrimmingEllipsisCharacter);
+        GdipSetStringFormatFlags(format_ptr, StringFormatFlagsNoWrap.0);
+        GdipSetTextRenderingHint(graphics_ptr, TextRenderingHintAntiAliasGridFit);
+
+        let text: Vec<u16> = text.encode_utf16().collect();
+        GdipDrawString(
+            graphics_ptr,
+            PCWSTR(text.as_ptr()),
+            text.len() as i32,
+            font_ptr,
+            &rect,
+            format_ptr,
+            brush_ptr as *mut GpBrush,
+        );
+
+        GdipDeleteStringFormat(format_ptr);
+        GdipDeleteBrush(brush_ptr as *mut GpBrush);
+        GdipDeleteFont(font_ptr);
+        GdipDeleteFontFamily(family_ptr);
+    }
+}
+
+/// Draws the icons into a bitmap of the size of [`Layout::icons_rect`].
+///
+/// GDI draws without antialiasing, so every icon cell is drawn at `SCALE_FACTOR` and scaled
+/// down on its own, which keeps the memory of a large grid small. Cells are drawn at 1x if the
+/// scaled bitmap can't be created. Returns `None` if the bitmap of the grid can't be created.
 fn draw_icons(
     state: &SwitchAppsState,
+    layout: &Layout,
     hdc_screen: HDC,
-    icon_size: i32,
-    icon_border: i32,
-    width: i32,
-    height: i32,
-    corner_radius: i32,
-    fg_color: u32,
-    bg_color: u32,
-) -> HBITMAP {
-    let scaled_width = width * SCALE_FACTOR;
-    let scaled_height = height * SCALE_FACTOR;
-    let scaled_corner_radius = corner_radius * SCALE_FACTOR;
-    let scaled_border_size = icon_border * SCALE_FACTOR;
-    let scaled_icon_inner_size = icon_size * SCALE_FACTOR;
-    let scaled_icon_outer_size = scaled_icon_inner_size + scaled_border_size * 2;
+    colors: &ThemeColors,
+    separator: u32,
+) -> Option<HBITMAP> {
+    let icons = layout.icons_rect();
+    let width = icons.right - icons.left;
+    let height = icons.bottom - icons.top;
+    let cell_size = layout.icon_size + layout.icon_border * 2;
+    let scaled_cell_size = cell_size * SCALE_FACTOR;
 
     unsafe {
         let hdc_tmp = CreateCompatibleDC(Some(hdc_screen));
         let bitmap_tmp = CreateCompatibleBitmap(hdc_screen, width, height);
+        if bitmap_tmp.is_invalid() {
+            error!("Failed to create the bitmap of the icons, {width}x{height}");
+            let _ = DeleteDC(hdc_tmp);
+            return None;
+        }
         SelectObject(hdc_tmp, bitmap_tmp.into());
+        SetStretchBltMode(hdc_tmp, HALFTONE);
 
         let hdc_scaled = CreateCompatibleDC(Some(hdc_screen));
-        let bitmap_scaled = CreateCompatibleBitmap(hdc_screen, scaled_width, scaled_height);
-        SelectObject(hdc_scaled, bitmap_scaled.into());
+        let bitmap_scaled = CreateCompatibleBitmap(hdc_screen, scaled_cell_size, scaled_cell_size);
+        let scaled = !bitmap_scaled.is_invalid();
+        if scaled {
+            SelectObject(hdc_scaled, bitmap_scaled.into());
+        } else {
+            warn!("Failed to create the scaled bitmap of an icon, the icons are not antialiased");
+        }
 
-        let fg_brush = CreateSolidBrush(COLORREF(fg_color));
-        let bg_brush = CreateSolidBrush(COLORREF(bg_color));
+        // GDI has no alpha, the bitmap is faded as a whole when it is drawn
+        let background = ALPHA_MASK | colors.background;
+        let fg_brush = CreateSolidBrush(to_colorref(blend_color(colors.selection, background)));
+        let bg_brush = CreateSolidBrush(to_colorref(background));
 
         let rect = RECT {
             left: 0,
             top: 0,
-            right: scaled_width,
-            bottom: scaled_height,
+            right: width,
+            bottom: height,
         };
-
-        FillRect(hdc_scaled, &rect, bg_brush);
-
-        for (i, (icon, _)) in state.apps.iter().enumerate() {
-            // draw the box for selected icon
-            if i == state.index {
-                let left = scaled_icon_outer_size * (i as i32);
-                let top = 0;
-                let right = left + scaled_icon_outer_size;
-                let bottom = top + scaled_icon_outer_size;
-                let rgn = CreateRoundRectRgn(
+        FillRect(hdc_tmp, &rect, bg_brush);
+
+        for (i, ((icon, _), item)) in state.apps.iter().zip(&layout.items).enumerate() {
+            let left = item.left - icons.left;
+            let top = item.top - icons.top;
+            let selected = i == state.index;
+            if scaled {
+                let rect = RECT {
+                    left: 0,
+                    top: 0,
+                    right: scaled_cell_size,
+                    bottom: scaled_cell_size,
+                };
+                FillRect(hdc_scaled, &rect, bg_brush);
+                draw_icon_cell(
+                    hdc_scaled,
+                    POINT::default(),
+                    SCALE_FACTOR,
+                    layout,
+                    *icon,
+                    selected,
+                    fg_brush,
+                );
+                let _ = StretchBlt(
+                    hdc_tmp,
                     left,
                     top,
-                    right,
-                    bottom,
-                    scaled_corner_radius,
-                    scaled_corner_radius,
+                    cell_size,
+                    cell_size,
+                    Some(hdc_scaled),
+                    0,
+                    0,
+                    scaled_cell_size,
+                    scaled_cell_size,
+                    SRCCOPY,
                 );
-                let _ = FillRgn(hdc_scaled, rgn, fg_brush);
-                let _ = DeleteObject(rgn.into());
+            } else {
+                let origin = POINT { x: left, y: top };
+                draw_icon_cell(hdc_tmp, origin, 1, layout, *icon, selected, fg_brush);
             }
-
-            let cx = scaled_border_size + scaled_icon_outer_size * (i as i32);
-            let _ = DrawIconEx(
-                hdc_scaled,
-                cx,
-                scaled_border_size,
-                *icon,
-                scaled_icon_inner_size,
-                scaled_icon_inner_size,
-                0,
-                None,
-                DI_NORMAL,
-            );
         }
 
-        SetStretchBltMode(hdc_tmp, HALFTONE);
-        let _ = StretchBlt(
-            hdc_tmp,
-            0,
-            0,
-            width,
-            height,
-            Some(hdc_scaled),
-            0,
-            0,
-            scaled_width,
-            scaled_height,
-            SRCCOPY,
-        );
+        if let Some(rect) = layout.separator(state.pinned) {
+            let rect = RECT {
+                left: rect.left - icons.left,
+                top: rect.top - icons.top,
+                right: rect.right - icons.left,
+                bottom: rect.bottom - icons.top,
+            };
+            let brush = CreateSolidBrush(to_colorref(separator));
+            FillRect(hdc_tmp, &rect, brush);
+            let _ = DeleteObject(brush.into());
+        }
 
         let _ = DeleteObject(fg_brush.into());
         let _ = DeleteObject(bg_brush.into());
-        let _ = DeleteObject(bitmap_scaled.into());
+        if scaled {
+            let _ = DeleteObject(bitmap_scaled.into());
+        }
         let _ = DeleteDC(hdc_scaled);
         let _ = DeleteDC(hdc_tmp);
 
-        bitmap_tmp
+        Some(bitmap_tmp)
     }
 }
 
-fn get_dpi_scale(hwnd: HWND) -> f64 {
+/// Draws an icon with its selection box at `origin`, with the sizes of `layout` times `scale`.
+unsafe fn draw_icon_cell(
+    hdc: HDC,
+    origin: POINT,
+    scale: i32,
+    layout: &Layout,
+    icon: HICON,
+    selected: bool,
+    fg_brush: HBRUSH,
+) {
     unsafe {
-        let dpi = GetDpiForWindow(hwnd);
-        if dpi == 0 {
-            1.0
-        } else {
-            dpi as f64 / 96.0
+        let POINT { x: left, y: top } = origin;
+        let border_size = layout.icon_border * scale;
+        let icon_size = layout.icon_size * scale;
+        if selected {
+            let cell_size = icon_size + border_size * 2;
+            let corner_radius = layout.corner_radius * scale;
+            let rgn = CreateRoundRectRgn(
+                left,
+                top,
+                left + cell_size,
+                top + cell_size,
+                corner_radius,
+                corner_radius,
+            );
+            let _ = FillRgn(hdc, rgn, fg_brush);
+            let _ = DeleteObject(rgn.into());
         }
+        let _ = DrawIconEx(
+            hdc,
+            left + border_size,
+            top + border_size,
+            icon,
+            icon_size,
+            icon_size,
+            0,
+            None,
+            DI_NORMAL,
+        );
     }
 }
 
-struct Coordinate {
-    x: i32,
-    y: i32,
-    width: i32,
-    height: i32,
-    icon_size: i32,
-    item_size: i32,
+fn layout_style(config: &Config, rounded_corner: bool) -> LayoutStyle {
+    LayoutStyle {
+        icon_size: config.theme.icon_size,
+        max_icons_per_row: config.switch_apps_max_icons_per_row,
+        min_icon_size: config.switch_apps_min_icon_size,
+        corner_radius: match config.theme.corner_radius {
+            None if !rounded_corner => Some(0),
+            v => v,
+        },
+        ..Default::default()
+    }
 }
 
-impl Coordinate {
-    fn new(num_apps: i32, icon_size_max: i32, border_size: i32, icon_border: i32) -> Self {
-        let monitor_rect = get_moinitor_rect();
-        let monitor_width = monitor_rect.right - monitor_rect.left;
-        let monitor_height = monitor_rect.bottom - monitor_rect.top;
-
-        let icon_size =
-            ((monitor_width - 2 * border_size) / num_apps - icon_border * 2).min(icon_size_max);
-
-        let item_size = icon_size + icon_border * 2;
-        let width = item_size * num_apps + border_size * 2;
-        let height = item_size + border_size * 2;
-        let x = monitor_rect.left + (monitor_width - width) / 2;
-        let y = monitor_rect.top + (monitor_height - height) / 2;
-
-        Self {
-            x,
-            y,
-            width,
-            height,
-            icon_size,
-            item_size,
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colors() {
+        assert_eq!(to_colorref(0xff112233), COLORREF(0x332211));
+        assert_eq!(blend_color(0xff112233, 0xff000000), 0xff112233);
+        assert_eq!(blend_color(0x00112233, 0xff445566), 0xff445566);
+        assert_eq!(blend_color(0x80ffffff, 0xff000000), 0xff808080);
     }
 }
@@ -0,0 +1,514 @@
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Applied after the user rules, so any of them can be overridden by an `include` rule.
+static BUILTIN_RULES: LazyLock<Vec<Rule>> = LazyLock::new(|| {
+    [
+        "style=tool",
+        "style=topmost",
+        "title=^$",
+        "title=^Windows Input Experience$",
+    ]
+    .iter()
+    .map(|v| Rule::parse(RuleAction::Exclude, v).unwrap())
+    .collect()
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleAction {
+    Include,
+    Exclude,
+}
+
+impl RuleAction {
+    pub const ALL: [RuleAction; 2] = [RuleAction::Include, RuleAction::Exclude];
+
+    /// The config key of the action.
+    pub fn name(self) -> &'static str {
+        match self {
+            RuleAction::Include => "include",
+            RuleAction::Exclude => "exclude",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StyleFlag {
+    Topmost,
+    Tool,
+    Minimized,
+}
+
+/// What the rules see of a window.
+#[derive(Debug, Default)]
+pub struct WindowInfo<'a> {
+    pub exe: &'a str,
+    pub class: &'a str,
+    pub title: &'a str,
+    pub is_topmost: bool,
+    pub is_tool: bool,
+    pub is_iconic: bool,
+}
+
+/// Includes or excludes the windows matching its conditions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub action: RuleAction,
+    conditions: Conditions,
+}
+
+impl Rule {
+    /// Parses conditions separated by `;`, e.g. `exe=*\slack.exe; style=topmost`.
+    pub fn parse(action: RuleAction, value: &str) -> Result<Self, String> {
+        Ok(Rule {
+            action,
+            conditions: Conditions::parse(value)?,
+        })
+    }
+
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        self.conditions.matches(window)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupAction {
+    /// Puts all matching windows into one app, keyed by the rule so that the key doesn't
+    /// change with the order of the windows.
+    Merge,
+    /// Makes an app of each title, or of the first capture group of the title regex.
+    Split,
+    /// Makes an app of each AppUserModelID, windows without one or already keyed by
+    /// a browser profile or PWA keep their app.
+    Aumid,
+}
+
+impl GroupAction {
+    pub const ALL: [GroupAction; 3] = [GroupAction::Merge, GroupAction::Split, GroupAction::Aumid];
+
+    /// The config key of the action.
+    pub fn name(self) -> &'static str {
+        match self {
+            GroupAction::Merge => "merge",
+            GroupAction::Split => "split",
+            GroupAction::Aumid => "aumid",
+        }
+    }
+}
+
+/// Changes the app of the windows matching its conditions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupRule {
+    pub action: GroupAction,
+    conditions: Conditions,
+}
+
+impl GroupRule {
+    /// Parses conditions the same way as [`Rule::parse`].
+    pub fn parse(action: GroupAction, value: &str) -> Result<Self, String> {
+        Ok(GroupRule {
+            action,
+            conditions: Conditions::parse(value)?,
+        })
+    }
+}
+
+/// Assigns windows to apps, see [`GroupRule`].
+pub struct Grouper<'a> {
+    groups: &'a [GroupRule],
+    /// The keys the windows matched by each merge rule would have without it, in the order
+    /// they were seen.
+    merged: Vec<Vec<String>>,
+}
+
+impl<'a> Grouper<'a> {
+    pub fn new(groups: &'a [GroupRule]) -> Self {
+        Self {
+            groups,
+            merged: vec![vec![]; groups.len()],
+        }
+    }
+
+    /// The key of each merged app with the keys of its members, the first of which
+    /// gives the app its icon and executable.
+    pub fn into_merged(self) -> impl Iterator<Item = (String, Vec<String>)> {
+        self.merged
+            .into_iter()
+            .enumerate()
+            .filter(|(_, keys)| !keys.is_empty())
+            .map(|(i, keys)| (merged_key(i), keys))
+    }
+
+    /// Returns the key of the app of the window, `key` is its key without group rules.
+    pub fn key(
+        &mut self,
+        window: &WindowInfo,
+        aumid: impl FnOnce() -> String,
+        key: String,
+    ) -> String {
+        let Some((i, group)) = self
+            .groups
+            .iter()
+            .enumerate()
+            .find(|(_, v)| v.conditions.matches(window))
+        else {
+            return key;
+        };
+        match group.action {
+            GroupAction::Merge => {
+                if !self.merged[i].contains(&key) {
+                    self.merged[i].push(key);
+                }
+                merged_key(i)
+            }
+            GroupAction::Split => {
+                let title = match &group.conditions.title {
+                    Some(regex) => regex
+                        .captures(window.title)
+                        .and_then(|v| v.get(1).or_else(|| v.get(0)))
+                        .map_or(window.title, |v| v.as_str()),
+                    None => window.title,
+                };
+                format!("{key}::title::{title}")
+            }
+            GroupAction::Aumid => {
+                let aumid = if key == window.exe {
+                    aumid()
+                } else {
+                    String::new()
+                };
+                if aumid.is_empty() {
+                    key
+                } else {
+                    format!("{}::aumid::{aumid}", window.exe)
+                }
+            }
+        }
+    }
+}
+
+/// The key of the app made by the merge rule at `index`.
+fn merged_key(index: usize) -> String {
+    format!("::group::{index}")
+}
+
+/// All conditions of a rule, empty ones match any window.
+#[derive(Debug, Clone)]
+struct Conditions {
+    /// Lowercase globs, matched against the file name unless they contain a path separator.
+    exe: Vec<String>,
+    /// Lowercase globs.
+    class: Vec<String>,
+    title: Option<Regex>,
+    /// Flags which must be set, or unset if `false`.
+    style: Vec<(StyleFlag, bool)>,
+}
+
+impl PartialEq for Conditions {
+    fn eq(&self, other: &Self) -> bool {
+        self.exe == other.exe
+            && self.class == other.class
+            && self.title.as_ref().map(Regex::as_str) == other.title.as_ref().map(Regex::as_str)
+            && self.style == other.style
+    }
+}
+
+impl Eq for Conditions {}
+
+impl Conditions {
+    fn parse(value: &str) -> Result<Self, String> {
+        let mut conditions = Conditions {
+            exe: vec![],
+            class: vec![],
+            title: None,
+            style: vec![],
+        };
+        let mut empty = true;
+        for condition in value.split(';').map(str::trim).filter(|v| !v.is_empty()) {
+            let (name, pattern) = condition.split_once('=').ok_or_else(|| {
+                format!("malformed condition '{condition}', expected name=pattern")
+            })?;
+            let pattern = pattern.trim();
+            if pattern.is_empty() {
+                return Err(format!("empty condition '{}'", name.trim()));
+            }
+            match name.trim() {
+                "exe" => conditions.exe = split_globs(&pattern.replace("\\\\", "\\")),
+                "class" => conditions.class = split_globs(pattern),
+                "title" => {
+                    let regex = Regex::new(pattern)
+                        .map_err(|_| format!("invalid title regex '{pattern}'"))?;
+                    conditions.title = Some(regex);
+                }
+                "style" => {
+                    for flag in pattern.split(',').map(str::trim) {
+                        let (name, value) = match flag.strip_prefix('!') {
+                            Some(name) => (name.trim(), false),
+                            None => (flag, true),
+                        };
+                        let flag = match name {
+                            "topmost" => StyleFlag::Topmost,
+                            "tool" => StyleFlag::Tool,
+                            "minimized" => StyleFlag::Minimized,
+                            _ => {
+                                return Err(format!(
+                                    "unknown style '{name}', expected topmost/tool/minimized"
+                                ))
+                            }
+                        };
+                        conditions.style.push((flag, value));
+                    }
+                }
+                name => {
+                    return Err(format!(
+                        "unknown condition '{name}', expected exe/class/title/style"
+                    ))
+                }
+            }
+            empty = false;
+        }
+        if empty {
+            return Err("empty rule, expected conditions such as exe=app.exe".into());
+        }
+        Ok(conditions)
+    }
+
+    fn matches(&self, window: &WindowInfo) -> bool {
+        let exe = window.exe.to_lowercase();
+        let exe_name = exe.rsplit(['\\', '/']).next().unwrap_or_default();
+        let exe_matches = |pattern: &String| {
+            if pattern.contains(['\\', '/']) {
+                glob_match(pattern, &exe)
+            } else {
+                glob_match(pattern, exe_name)
+            }
+        };
+        let class = window.class.to_lowercase();
+        (self.exe.is_empty() || self.exe.iter().any(exe_matches))
+            && (self.class.is_empty() || self.class.iter().any(|v| glob_match(v, &class)))
+            && self.title.as_ref().is_none_or(|v| v.is_match(window.title))
+            && self.style.iter().all(|(flag, value)| {
+                let set = match flag {
+                    StyleFlag::Topmost => window.is_topmost,
+                    StyleFlag::Tool => window.is_tool,
+                    StyleFlag::Minimized => window.is_iconic,
+                };
+                set == *value
+            })
+    }
+}
+
+/// Whether the window is listed: the first matching rule decides, the user rules first,
+/// and windows matching no rule are listed.
+pub fn is_window_included(rules: &[Rule], window: &WindowInfo) -> bool {
+    rules
+        .iter()
+        .chain(BUILTIN_RULES.iter())
+        .find(|v| v.matches(window))
+        .is_none_or(|v| v.action == RuleAction::Include)
+}
+
+/// Splits lowercase globs separated by `,`, any of them may match.
+fn split_globs(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Matches `*` against any characters and `?` against one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // where the last `*` is and how much of the text it has taken
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|v| *v == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SLACK: &str = r"C:\Users\sigod\AppData\Local\slack\slack.exe";
+
+    fn window(title: &str) -> WindowInfo<'_> {
+        WindowInfo {
+            exe: SLACK,
+            class: "Chrome_WidgetWin_1",
+            title,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("slack.exe", "slack.exe"));
+        assert!(glob_match("*.exe", "slack.exe"));
+        assert!(glob_match("s?ack*", "slack.exe"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match(r"c:\*\slack\*", &SLACK.to_lowercase()));
+        assert!(!glob_match("*.exe", "slack.dll"));
+        assert!(!glob_match("slack", "slack.exe"));
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        assert!(Rule::parse(RuleAction::Include, "exe=slack.exe; style=topmost,!tool").is_ok());
+        assert_eq!(
+            Rule::parse(RuleAction::Include, "").unwrap_err(),
+            "empty rule, expected conditions such as exe=app.exe"
+        );
+        assert_eq!(
+            Rule::parse(RuleAction::Include, "exe").unwrap_err(),
+            "malformed condition 'exe', expected name=pattern"
+        );
+        assert_eq!(
+            Rule::parse(RuleAction::Include, "pid=1").unwrap_err(),
+            "unknown condition 'pid', expected exe/class/title/style"
+        );
+        assert_eq!(
+            Rule::parse(RuleAction::Include, "exe= ; title=a").unwrap_err(),
+            "empty condition 'exe'"
+        );
+        assert_eq!(
+            Rule::parse(RuleAction::Include, "style=hidden").unwrap_err(),
+            "unknown style 'hidden', expected topmost/tool/minimized"
+        );
+        assert_eq!(
+            Rule::parse(RuleAction::Include, "title=(").unwrap_err(),
+            "invalid title regex '('"
+        );
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let rule = |v| Rule::parse(RuleAction::Exclude, v).unwrap();
+        assert!(rule("exe=SLACK.EXE").matches(&window("Slack")));
+        assert!(rule(r"exe=*\\slack\\*.exe").matches(&window("Slack")));
+        assert!(!rule(r"exe=*\teams\*").matches(&window("Slack")));
+        assert!(rule("class=chrome_*").matches(&window("Slack")));
+        assert!(rule("exe=teams.exe, slack.exe").matches(&window("Slack")));
+        assert!(rule("title=^Sla").matches(&window("Slack")));
+        assert!(!rule("title=^sla").matches(&window("Slack")));
+        assert!(rule("title=(?i)^sla").matches(&window("Slack")));
+        // all conditions must match
+        assert!(!rule("exe=slack.exe; title=Huddle").matches(&window("Slack")));
+
+        let topmost = WindowInfo {
+            is_topmost: true,
+            ..window("Slack")
+        };
+        assert!(rule("style=topmost").matches(&topmost));
+        assert!(!rule("style=topmost,!minimized").matches(&WindowInfo {
+            is_iconic: true,
+            ..window("Slack")
+        }));
+    }
+
+    #[test]
+    fn test_is_window_included() {
+        let topmost = WindowInfo {
+            is_topmost: true,
+            ..window("Huddle")
+        };
+        assert!(is_window_included(&[], &window("Slack")));
+        assert!(!is_window_included(&[], &window("")));
+        assert!(!is_window_included(&[], &topmost));
+
+        // user rules come first and the first match wins
+        let rules = vec![
+            Rule::parse(RuleAction::Exclude, "title=Huddle; style=!topmost").unwrap(),
+            Rule::parse(RuleAction::Include, "exe=slack.exe; style=topmost").unwrap(),
+            Rule::parse(RuleAction::Exclude, "exe=slack.exe").unwrap(),
+        ];
+        assert!(is_window_included(&rules, &topmost));
+        assert!(!is_window_included(&rules, &window("Huddle")));
+        assert!(!is_window_included(&rules, &window("Slack")));
+    }
+
+    #[test]
+    fn test_grouper() {
+        let groups = vec![
+            GroupRule::parse(GroupAction::Split, r"exe=slack.exe; title=^(.+) - Huddle$").unwrap(),
+            GroupRule::parse(GroupAction::Merge, r"exe=*\\slack\\app-*\\slack.exe").unwrap(),
+            GroupRule::parse(GroupAction::Aumid, "exe=javaw.exe").unwrap(),
+            GroupRule::parse(GroupAction::Split, "exe=winword.exe").unwrap(),
+        ];
+        let mut grouper = Grouper::new(&groups);
+        let mut key = |exe: &str, title: &str, aumid: &str| {
+            let window = WindowInfo {
+                exe,
+                title,
+                ..Default::default()
+            };
+            grouper.key(&window, || aumid.to_string(), exe.to_string())
+        };
+        let old = r"C:\Users\sigod\AppData\Local\slack\app-4.1.0\slack.exe";
+        let new = r"C:\Users\sigod\AppData\Local\slack\app-4.2.0\slack.exe";
+        assert_eq!(key(new, "Slack", ""), "::group::1");
+        assert_eq!(key(old, "Slack", ""), "::group::1");
+        assert_eq!(
+            key(old, "general - Huddle", ""),
+            format!("{old}::title::general")
+        );
+
+        let java = r"C:\Program Files\Java\bin\javaw.exe";
+        assert_eq!(
+            key(java, "IDE", "com.ide"),
+            format!("{java}::aumid::com.ide")
+        );
+        assert_eq!(key(java, "Tool", ""), java);
+
+        let word = r"C:\Program Files\Microsoft Office\WINWORD.EXE";
+        assert_eq!(key(word, "a.docx", ""), format!("{word}::title::a.docx"));
+        assert_eq!(key(SLACK, "Slack", ""), SLACK);
+        assert_eq!(key(new, "Huddle", ""), "::group::1");
+        assert_eq!(
+            grouper.into_merged().collect::<Vec<_>>(),
+            [(
+                "::group::1".to_string(),
+                vec![new.to_string(), old.to_string()]
+            )]
+        );
+
+        // the key doesn't depend on which member comes first
+        let mut grouper = Grouper::new(&groups);
+        let window = |exe| WindowInfo {
+            exe,
+            title: "Slack",
+            ..Default::default()
+        };
+        assert_eq!(
+            grouper.key(&window(old), String::new, old.to_string()),
+            "::group::1"
+        );
+        assert_eq!(
+            grouper.key(&window(new), String::new, new.to_string()),
+            "::group::1"
+        );
+        assert_eq!(
+            grouper.into_merged().collect::<Vec<_>>(),
+            [(
+                "::group::1".to_string(),
+                vec![old.to_string(), new.to_string()]
+            )]
+        );
+    }
+}
@@ -0,0 +1,1682 @@
+use crate::config::{Config, Program, WindowAction};
+use crate::fuzzy::fuzzy_score;
+use crate::keyboard::Direction;
+use crate::mru::MruHistory;
+use crate::rules::Grouper;
+use crate::utils::{list_windows, WindowSystem};
+
+use crate::win32::{HICON, HMONITOR, HWND};
+use anyhow::Result;
+use indexmap::{IndexMap, IndexSet};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// The platform-independent part of the switcher.
+///
+/// It owns the switching state and talks to the desktop only through a [`WindowSystem`].
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct Switcher<W: WindowSystem> {
+    ws: W,
+    config: Config,
+    is_admin: bool,
+    mru: &'static Mutex<MruHistory>,
+    switch_windows_state: SwitchWindowsState,
+    switch_apps_state: Option<SwitchAppsState>,
+    cached_icons: HashMap<String, HICON>,
+    product_names: HashMap<String, String>,
+    /// The keys of the members of each merged app, see [`Grouper::into_merged`].
+    merged_members: HashMap<String, Vec<String>>,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl<W: WindowSystem> Switcher<W> {
+    /// Apps and windows are ordered by `mru`, which is fed by the foreground watcher.
+    pub fn new(ws: W, config: Config, is_admin: bool, mru: &'static Mutex<MruHistory>) -> Self {
+        Self {
+            ws,
+            config,
+            is_admin,
+            mru,
+            switch_windows_state: SwitchWindowsState {
+                cache: None,
+                modifier_released: true,
+            },
+            switch_apps_state: None,
+            cached_icons: Default::default(),
+            product_names: Default::default(),
+            merged_members: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Replaces the config, dropping the cached icons and any switching in progress.
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+        self.switch_windows_state = SwitchWindowsState {
+            cache: None,
+            modifier_released: true,
+        };
+        self.switch_apps_state = None;
+        self.merged_members.clear();
+        for (_, icon) in self.cached_icons.drain() {
+            self.ws.destroy_icon(icon);
+        }
+    }
+
+    pub fn switch_apps_state(&self) -> Option<&SwitchAppsState> {
+        self.switch_apps_state.as_ref()
+    }
+
+    /// Cycles the windows of the app which is selected in the switcher or owns the foreground window.
+    pub fn switch_windows(&mut self, reverse: bool) -> Result<bool> {
+        let hwnd = self
+            .switch_apps_state
+            .as_ref()
+            .and_then(|state| state.apps.get(state.index).map(|(_, id)| *id))
+            .unwrap_or_else(|| self.ws.get_foreground_window());
+        self.switch_windows_of(hwnd, reverse)
+    }
+
+    pub fn switch_windows_done(&mut self) {
+        self.switch_windows_state.modifier_released = true;
+    }
+
+    fn switch_windows_of(&mut self, hwnd: HWND, reverse: bool) -> Result<bool> {
+        let windows = self.list_windows(
+            self.config.switch_windows_ignore_minimal,
+            self.config.switch_windows_only_current_desktop(),
+            self.current_monitor(self.config.switch_windows_only_current_monitor),
+        )?;
+        debug!(
+            "switch windows: hwnd:{hwnd:?} reverse:{reverse} state:{:?}",
+            self.switch_windows_state
+        );
+        let Some((module_path, windows)) = windows
+            .into_iter()
+            .find(|(_, v)| v.iter().any(|(id, _)| *id == hwnd))
+        else {
+            return Ok(false);
+        };
+        let windows: Vec<HWND> = windows.into_iter().map(|(v, _)| v).collect();
+        Ok(self.cycle_windows(module_path, &windows, reverse))
+    }
+
+    /// Cycles every window in MRU order, not just the windows of one app.
+    ///
+    /// With `all_overlay` enabled, the switcher is shown with an entry for each window instead.
+    pub fn switch_all_windows(&mut self, reverse: bool) -> Result<bool> {
+        if self.config.switch_windows_all_overlay {
+            if self.switch_apps_state.is_some() {
+                self.switch_apps(reverse)?;
+                return Ok(true);
+            }
+            let monitor = self.current_monitor(self.config.switch_windows_only_current_monitor);
+            let entries = self.window_entries(&[], monitor)?;
+            return Ok(self.open_switcher(entries, reverse, monitor, true));
+        }
+        let windows = self.list_windows(
+            self.config.switch_windows_ignore_minimal,
+            self.config.switch_windows_only_current_desktop(),
+            self.current_monitor(self.config.switch_windows_only_current_monitor),
+        )?;
+        debug!(
+            "switch all windows: reverse:{reverse} state:{:?}",
+            self.switch_windows_state
+        );
+        let mut windows: Vec<HWND> = windows.into_values().flatten().map(|(v, _)| v).collect();
+        self.sort_by_mru(&mut windows, |v| *v);
+        Ok(self.cycle_windows(ALL_WINDOWS_KEY.to_string(), &windows, reverse))
+    }
+
+    /// Runs the launcher at `index`: focuses the most recent window of its program, cycles
+    /// the windows of the program if one of them is active already, or starts it.
+    pub fn launch(&mut self, index: usize) -> Result<()> {
+        let Some(program) = self.config.launchers.get(index).map(|v| v.program.clone()) else {
+            return Ok(());
+        };
+        // any running window of the program, even minimized or on another desktop or monitor,
+        // keeps a second instance from starting
+        let windows = self.list_windows(false, false, None)?;
+        let mut groups: Vec<(String, Vec<HWND>)> = if program.is_aumid() {
+            // windows of a packaged app may belong to any app, such as a shared host
+            let hwnds: Vec<HWND> = windows
+                .iter()
+                .flat_map(|(key, hwnds)| {
+                    hwnds
+                        .iter()
+                        .map(|(v, _)| *v)
+                        .filter(|v| self.is_program_window(&program, key, *v))
+                })
+                .collect();
+            vec![(format!("::aumid::{}", program.target), hwnds)]
+        } else {
+            windows
+                .into_iter()
+                .filter(|(key, hwnds)| self.is_program_window(&program, key, hwnds[0].0))
+                .map(|(key, hwnds)| (key, hwnds.into_iter().map(|(v, _)| v).collect()))
+                .collect()
+        };
+        groups.retain(|(_, hwnds)| !hwnds.is_empty());
+        debug!(
+            "launch: target:{} groups:{groups:?} state:{:?}",
+            program.target, self.switch_windows_state
+        );
+        let foreground = self.ws.get_foreground_window();
+        let i = groups
+            .iter()
+            .position(|(_, hwnds)| hwnds.contains(&foreground))
+            .unwrap_or_default();
+        let Some((key, mut hwnds)) = groups.into_iter().nth(i) else {
+            return self.ws.launch(&program.shell_path());
+        };
+        self.sort_by_mru(&mut hwnds, |v| *v);
+        if hwnds[0] == foreground {
+            self.cycle_windows(key, &hwnds, false);
+        } else {
+            self.activate(hwnds[0]);
+        }
+        Ok(())
+    }
+
+    /// Whether the window of the app with this key belongs to the program.
+    fn is_program_window(&self, program: &Program, key: &str, hwnd: HWND) -> bool {
+        if program.is_aumid() {
+            self.ws
+                .get_aumid(hwnd)
+                .is_some_and(|v| v.eq_ignore_ascii_case(&program.target))
+        } else {
+            // browser profiles and split apps are keyed by `<module_path>::<id>`
+            self.member_keys(key)
+                .iter()
+                .any(|v| program.matches_module_path(v.split("::").next().unwrap_or_default()))
+        }
+    }
+
+    /// Activates the next window, the first one being the current window. While the modifier
+    /// is held the order is kept, so that repeated presses walk through every window.
+    fn cycle_windows(&mut self, key: String, windows: &[HWND], reverse: bool) -> bool {
+        let windows_len = windows.len();
+        if windows_len <= 1 {
+            return false;
+        }
+        let current_id = windows[0];
+        let mut index = 1;
+        let mut state_id = current_id;
+        let mut state_windows = vec![];
+        if windows_len > 2 {
+            if let Some((cache_key, cache_id, cache_index, cache_windows)) =
+                self.switch_windows_state.cache.as_ref()
+            {
+                if cache_key == &key {
+                    if self.switch_windows_state.modifier_released {
+                        if *cache_id != current_id {
+                            if let Some(i) = windows.iter().position(|v| v == cache_id) {
+                                index = i;
+                            }
+                        }
+                    } else {
+                        state_id = *cache_id;
+                        let mut windows_set: IndexSet<isize> =
+                            windows.iter().map(|v| v.0 as _).collect();
+                        for id in cache_windows {
+                            if windows_set.contains(id) {
+                                state_windows.push(*id);
+                                windows_set.swap_remove(id);
+                            }
+                        }
+                        state_windows.extend(windows_set);
+                        index = if reverse {
+                            if *cache_index == 0 {
+                                windows_len - 1
+                            } else {
+                                cache_index - 1
+                            }
+                        } else if *cache_index >= windows_len - 1 {
+                            0
+                        } else {
+                            cache_index + 1
+                        };
+                    }
+                }
+            }
+        }
+        if state_windows.is_empty() {
+            state_windows = windows.iter().map(|v| v.0 as _).collect();
+        }
+        let hwnd = HWND(state_windows[index] as _);
+        self.switch_windows_state = SwitchWindowsState {
+            cache: Some((key, state_id, index, state_windows)),
+            modifier_released: false,
+        };
+        self.activate(hwnd);
+        true
+    }
+
+    pub fn switch_apps(&mut self, reverse: bool) -> Result<()> {
+        debug!(
+            "switch apps: reverse:{reverse}, state:{:?}",
+            self.switch_apps_state
+        );
+        if let Some(state) = self.switch_apps_state.as_mut() {
+            if state.apps.is_empty() {
+                return Ok(());
+            }
+            state.window_list = None;
+            if reverse {
+                if state.index == 0 {
+                    state.index = state.apps.len() - 1;
+                } else {
+                    state.index -= 1;
+                }
+            } else if state.index == state.apps.len() - 1 {
+                state.index = 0;
+            } else {
+                state.index += 1;
+            };
+            debug!("switch apps: new index:{}", state.index);
+            return Ok(());
+        }
+        let monitor = self.current_monitor(self.config.switch_apps_only_current_monitor);
+        let entries = self.app_entries(&[], monitor)?;
+        self.open_switcher(entries, reverse, monitor, false);
+        Ok(())
+    }
+
+    /// Shows the switcher with the entries, selecting the one after the current app or window.
+    fn open_switcher(
+        &mut self,
+        mut entries: Vec<AppEntry>,
+        reverse: bool,
+        monitor: Option<HMONITOR>,
+        all_windows: bool,
+    ) -> bool {
+        // the entries are in MRU order until the pinned apps are moved to the front
+        let index = if entries.len() <= 1 {
+            0
+        } else if reverse {
+            entries.len() - 1
+        } else {
+            1
+        };
+        let selected = entries.get(index).map(|v| v.hwnd);
+        let pinned_entries = if all_windows {
+            0
+        } else {
+            self.pin_entries(&mut entries)
+        };
+        // closed pinned apps can still be started when no window is open
+        if entries.is_empty() {
+            return false;
+        }
+
+        let mut state = SwitchAppsState {
+            apps: vec![],
+            index: 0,
+            query: String::new(),
+            show_title: all_windows || self.config.switch_apps_window_list,
+            window_list: None,
+            columns: 0,
+            pinned: 0,
+            entries,
+            matched: vec![],
+            pinned_entries,
+            monitor,
+            all_windows,
+        };
+        state.filter();
+        state.index = state
+            .apps
+            .iter()
+            .position(|(_, hwnd)| Some(*hwnd) == selected)
+            .unwrap_or_default();
+        self.switch_apps_state = Some(state);
+        debug!("switch apps, new state:{:?}", self.switch_apps_state);
+        true
+    }
+
+    /// Appends a typed character to the search query of the app switcher.
+    pub fn search_input(&mut self, c: char) {
+        if let Some(state) = self.switch_apps_state.as_mut() {
+            state.query.push(c);
+            state.filter();
+        }
+    }
+
+    /// Removes the last character of the search query of the app switcher.
+    pub fn search_backspace(&mut self) {
+        if let Some(state) = self.switch_apps_state.as_mut() {
+            if state.query.pop().is_some() {
+                state.filter();
+            }
+        }
+    }
+
+    /// Handles the arrow keys.
+    ///
+    /// Left/Right cycle the apps and Up/Down move between the rows of the grid. With `window_list`
+    /// enabled, Down on the bottom row expands the selected app into the list of its windows and
+    /// Up/Down move within it. On a single row, Up/Down otherwise cycle the apps too.
+    pub fn navigate(&mut self, direction: Direction) -> Result<()> {
+        let window_list_enabled = self.config.switch_apps_window_list;
+        let Some(state) = self.switch_apps_state.as_mut() else {
+            return Ok(());
+        };
+        let len = state.apps.len();
+        if len == 0 {
+            return Ok(());
+        }
+        let columns = match state.columns {
+            0 => len.max(1),
+            v => v,
+        };
+        let single_row = len <= columns;
+        match (state.window_list.as_mut(), direction) {
+            (Some(list), Direction::Up) => {
+                if list.index == 0 {
+                    state.window_list = None;
+                } else {
+                    list.index -= 1;
+                }
+                Ok(())
+            }
+            (Some(list), Direction::Down) => {
+                list.index = (list.index + 1).min(list.windows.len() - 1);
+                Ok(())
+            }
+            (None, Direction::Up) if state.index >= columns => {
+                state.index -= columns;
+                Ok(())
+            }
+            (None, Direction::Down) if state.index / columns < (len - 1) / columns => {
+                // the last row may be shorter
+                state.index = (state.index + columns).min(len - 1);
+                Ok(())
+            }
+            (None, Direction::Down) if window_list_enabled => {
+                state.open_window_list();
+                Ok(())
+            }
+            (_, Direction::Left) => self.switch_apps(true),
+            (_, Direction::Right) => self.switch_apps(false),
+            (_, Direction::Up) if single_row => self.switch_apps(true),
+            (_, Direction::Down) if single_row => self.switch_apps(false),
+            _ => Ok(()),
+        }
+    }
+
+    /// Records how many icons fit in a row, as laid out by the painter.
+    pub fn set_columns(&mut self, columns: usize) {
+        if let Some(state) = self.switch_apps_state.as_mut() {
+            state.columns = columns;
+        }
+    }
+
+    /// Applies the action to the selected app, or to the selected window of its list,
+    /// then refreshes the switcher in place.
+    pub fn window_action(&mut self, action: WindowAction) -> Result<()> {
+        let Some(state) = self.switch_apps_state.as_ref() else {
+            return Ok(());
+        };
+        let Some(entry) = state.selected_entry().filter(|v| v.launch.is_none()) else {
+            return Ok(());
+        };
+        // a window picked from the list, otherwise the app's window
+        let selected = state.selected_hwnd().unwrap_or(entry.hwnd);
+        let all_windows = || entry.windows.iter().map(|(hwnd, _)| *hwnd).collect();
+        let targets: Vec<HWND> = match action {
+            WindowAction::CloseApp => all_windows(),
+            WindowAction::CloseWindow | WindowAction::Kill => vec![selected],
+            WindowAction::Minimize | WindowAction::Restore if state.window_list.is_some() => {
+                vec![selected]
+            }
+            WindowAction::Minimize | WindowAction::Restore => all_windows(),
+        };
+        debug!("window action: {action:?} {targets:?}");
+        let mut closed = vec![];
+        match action {
+            WindowAction::CloseApp | WindowAction::CloseWindow => {
+                for hwnd in targets {
+                    self.ws.close_window(hwnd);
+                    closed.push(hwnd);
+                }
+            }
+            WindowAction::Minimize => targets.iter().for_each(|v| self.ws.minimize_window(*v)),
+            WindowAction::Restore => targets.iter().for_each(|v| self.ws.restore_window(*v)),
+            WindowAction::Kill => {
+                let pid = self.ws.get_window_pid(selected);
+                if self.is_protected_process(pid) {
+                    warn!("Refused to kill process {pid}, closing its window instead");
+                    self.ws.close_window(selected);
+                    closed.push(selected);
+                } else {
+                    closed.extend(
+                        state
+                            .entries
+                            .iter()
+                            .flat_map(|v| v.windows.iter())
+                            .map(|(hwnd, _)| *hwnd)
+                            .filter(|hwnd| self.ws.get_window_pid(*hwnd) == pid),
+                    );
+                    self.ws.kill_process(pid)?;
+                }
+            }
+        }
+        let monitor = state.monitor;
+        let (entries, pinned_entries) = if state.all_windows {
+            (self.window_entries(&closed, monitor)?, 0)
+        } else {
+            let mut entries = self.app_entries(&closed, monitor)?;
+            let pinned_entries = self.pin_entries(&mut entries);
+            (entries, pinned_entries)
+        };
+        if let Some(state) = self.switch_apps_state.as_mut() {
+            state.refresh(entries, pinned_entries);
+        }
+        Ok(())
+    }
+
+    /// Whether killing the process would take more than its own windows with it: the shell,
+    /// the host of UWP apps, or the switcher itself.
+    fn is_protected_process(&self, pid: u32) -> bool {
+        if pid == std::process::id() {
+            return true;
+        }
+        let Some(module_path) = self.ws.get_module_path(pid) else {
+            return false;
+        };
+        let name = get_exe_name(&module_path);
+        ["explorer", "ApplicationFrameHost"]
+            .iter()
+            .any(|v| name.eq_ignore_ascii_case(v))
+    }
+
+    /// Selects the app at `index`, e.g. the one which was clicked.
+    pub fn select_app(&mut self, index: usize) {
+        if let Some(state) = self.switch_apps_state.as_mut() {
+            if index < state.apps.len() {
+                state.index = index;
+                state.window_list = None;
+            }
+        }
+    }
+
+    /// Selects the window at `index` of the expanded window list.
+    pub fn select_window(&mut self, index: usize) {
+        if let Some(list) = self
+            .switch_apps_state
+            .as_mut()
+            .and_then(|state| state.window_list.as_mut())
+        {
+            if index < list.windows.len() {
+                list.index = index;
+            }
+        }
+    }
+
+    /// Brings the selected app, or the selected window of its list, to the foreground
+    /// and returns the finished state. A pinned app without windows is started.
+    pub fn do_switch_app(&mut self) -> Option<SwitchAppsState> {
+        let state = self.switch_apps_state.take()?;
+        if let Some(path) = state.selected_entry().and_then(|v| v.launch.as_deref()) {
+            if let Err(err) = self.ws.launch(path) {
+                error!("{err}");
+            }
+        } else if let Some(hwnd) = state.selected_hwnd() {
+            self.activate(hwnd);
+        }
+        Some(state)
+    }
+
+    pub fn cancel_switch_app(&mut self) -> Option<SwitchAppsState> {
+        self.switch_apps_state.take()
+    }
+
+    /// Builds the entries of the app switcher, leaving out the `exclude` windows
+    /// which may still be around while they are closing.
+    fn app_entries(
+        &mut self,
+        exclude: &[HWND],
+        monitor: Option<HMONITOR>,
+    ) -> Result<Vec<AppEntry>> {
+        let windows = self.list_windows(
+            self.config.switch_apps_ignore_minimal,
+            self.config.switch_apps_only_current_desktop(),
+            monitor,
+        )?;
+        Ok(self.entries(windows, exclude, false))
+    }
+
+    /// Builds an entry for each window in MRU order, for switching all windows with the overlay.
+    fn window_entries(
+        &mut self,
+        exclude: &[HWND],
+        monitor: Option<HMONITOR>,
+    ) -> Result<Vec<AppEntry>> {
+        let windows = self.list_windows(
+            self.config.switch_windows_ignore_minimal,
+            self.config.switch_windows_only_current_desktop(),
+            monitor,
+        )?;
+        let mut entries = self.entries(windows, exclude, true);
+        self.sort_by_mru(&mut entries, |v| v.hwnd);
+        Ok(entries)
+    }
+
+    /// Moves the entries of the pinned apps to the front, in the order of the config, and
+    /// returns their number. Pinned apps without windows get an entry if `pinned_closed`.
+    fn pin_entries(&mut self, entries: &mut Vec<AppEntry>) -> usize {
+        let mut pinned = vec![];
+        for program in self.config.switch_apps_pinned.clone() {
+            let (matching, rest): (Vec<AppEntry>, Vec<AppEntry>) =
+                std::mem::take(entries).into_iter().partition(|v| {
+                    v.windows
+                        .iter()
+                        .any(|(hwnd, _)| self.is_program_window(&program, &v.key, *hwnd))
+                });
+            *entries = rest;
+            if matching.is_empty() && self.config.switch_apps_pinned_closed {
+                pinned.push(self.closed_entry(&program));
+            }
+            pinned.extend(matching);
+        }
+        let len = pinned.len();
+        entries.splice(0..0, pinned);
+        len
+    }
+
+    /// The entry of a pinned app which has no windows.
+    fn closed_entry(&mut self, program: &Program) -> AppEntry {
+        // AppsFolder icons are looked up by the `::aumid::` keys of `list_windows`
+        let (key, exe_path) = if program.is_aumid() {
+            (format!("::aumid::{}", program.target), "")
+        } else {
+            (program.target.clone(), program.target.as_str())
+        };
+        let icon = *self.cached_icons.entry(key.clone()).or_insert_with(|| {
+            self.ws.get_app_icon(
+                &self.config.switch_apps_override_icons,
+                &key,
+                HWND::default(),
+            )
+        });
+        let product_name = self
+            .product_names
+            .entry(exe_path.to_string())
+            .or_insert_with(|| self.ws.get_product_name(exe_path).unwrap_or_default())
+            .clone();
+        AppEntry {
+            key,
+            icon,
+            hwnd: HWND::default(),
+            exe: get_exe_name(&program.target),
+            product_name,
+            windows: vec![],
+            launch: Some(program.shell_path()),
+        }
+    }
+
+    /// Sorts windows of different apps by their last activation, the current window first
+    /// even if the foreground watcher has not seen it yet.
+    fn sort_by_mru<T>(&self, items: &mut [T], hwnd: impl Fn(&T) -> HWND) {
+        let mru = self.mru.lock();
+        items.sort_by_key(|v| mru.rank(hwnd(v)));
+        drop(mru);
+        let foreground = self.ws.get_foreground_window();
+        if let Some(i) = items.iter().position(|v| hwnd(v) == foreground) {
+            items[..=i].rotate_right(1);
+        }
+    }
+
+    /// Makes an entry of each app, or of each window if `per_window`.
+    fn entries(
+        &mut self,
+        mut windows: IndexMap<String, Vec<(HWND, String)>>,
+        exclude: &[HWND],
+        per_window: bool,
+    ) -> Vec<AppEntry> {
+        for hwnds in windows.values_mut() {
+            hwnds.retain(|(hwnd, _)| !exclude.contains(hwnd));
+        }
+        windows.retain(|_, hwnds| !hwnds.is_empty());
+        let mut entries = vec![];
+        for (module_path, hwnds) in windows.iter() {
+            let module_hwnd = if self.ws.is_iconic_window(hwnds[0].0) {
+                hwnds[hwnds.len() - 1].0
+            } else {
+                hwnds[0].0
+            };
+            let icon_key = self.member_keys(module_path)[0].to_string();
+            let module_hicon = self
+                .cached_icons
+                .entry(module_path.clone())
+                .or_insert_with(|| {
+                    self.ws.get_app_icon(
+                        &self.config.switch_apps_override_icons,
+                        &icon_key,
+                        module_hwnd,
+                    )
+                });
+            // browser profiles and PWAs are keyed by `<module_path>::<id>`
+            let exe_path = icon_key.split("::").next().unwrap_or_default();
+            let product_name = self
+                .product_names
+                .entry(exe_path.to_string())
+                .or_insert_with(|| self.ws.get_product_name(exe_path).unwrap_or_default());
+            let entry = |hwnd: HWND, windows: Vec<(HWND, String)>| AppEntry {
+                key: module_path.clone(),
+                icon: *module_hicon,
+                hwnd,
+                exe: get_exe_name(exe_path),
+                product_name: product_name.clone(),
+                windows,
+                launch: None,
+            };
+            if per_window {
+                entries.extend(hwnds.iter().map(|v| entry(v.0, vec![v.clone()])));
+            } else {
+                entries.push(entry(module_hwnd, hwnds.clone()));
+            }
+        }
+        entries
+    }
+
+    /// Every window the switchers could list, grouped by app.
+    pub fn list_all_windows(&mut self) -> Result<IndexMap<String, Vec<(HWND, String)>>> {
+        self.list_windows(false, false, None)
+    }
+
+    /// Our own activations are not seen by the foreground watcher, so record them here.
+    fn activate(&self, hwnd: HWND) {
+        self.ws.set_foreground_window(hwnd);
+        self.mru.lock().touch(hwnd);
+    }
+
+    /// The monitor with the foreground window, or the cursor if there is none, when `enabled`.
+    fn current_monitor(&self, enabled: bool) -> Option<HMONITOR> {
+        if !enabled {
+            return None;
+        }
+        let hwnd = self.ws.get_foreground_window();
+        let monitor = if hwnd.is_invalid() {
+            self.ws.get_cursor_monitor()
+        } else {
+            self.ws.get_window_monitor(hwnd)
+        };
+        Some(monitor)
+    }
+
+    fn list_windows(
+        &mut self,
+        ignore_minimal: bool,
+        only_current_desktop: bool,
+        monitor: Option<HMONITOR>,
+    ) -> Result<IndexMap<String, Vec<(HWND, String)>>> {
+        let mut grouper = Grouper::new(&self.config.groups);
+        let mut windows = list_windows(
+            &self.ws,
+            ignore_minimal,
+            only_current_desktop,
+            monitor,
+            &self.config.rules,
+            &mut grouper,
+            self.is_admin,
+        )?;
+        self.merged_members.extend(grouper.into_merged());
+        self.mru.lock().sort(&mut windows);
+        Ok(windows)
+    }
+
+    /// The keys the windows of the app have without merge rules, the first of them gives
+    /// a merged app its icon and executable. Other apps are their only member.
+    fn member_keys<'a>(&'a self, key: &'a str) -> Vec<&'a str> {
+        match self.merged_members.get(key) {
+            Some(keys) => keys.iter().map(String::as_str).collect(),
+            None => vec![key],
+        }
+    }
+}
+
+impl<W: WindowSystem> Drop for Switcher<W> {
+    fn drop(&mut self) {
+        for (_, icon) in self.cached_icons.drain() {
+            self.ws.destroy_icon(icon);
+        }
+    }
+}
+
+/// The key of [`SwitchWindowsState::cache`] when cycling all windows, no app has an empty key.
+#[cfg_attr(not(windows), allow(dead_code))]
+const ALL_WINDOWS_KEY: &str = "";
+
+#[derive(Debug)]
+#[cfg_attr(not(windows), allow(dead_code))]
+struct SwitchWindowsState {
+    cache: Option<(String, HWND, usize, Vec<isize>)>,
+    modifier_released: bool,
+}
+
+#[derive(Debug)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct SwitchAppsState {
+    /// The apps matching the query, best match first.
+    pub apps: Vec<(HICON, HWND)>,
+    pub index: usize,
+    pub query: String,
+    /// Whether the title of the selection is shown under the icons.
+    pub show_title: bool,
+    /// The windows of the selected app, when it is expanded.
+    pub window_list: Option<WindowList>,
+    /// The number of icons in a row, 0 until the apps have been laid out.
+    pub columns: usize,
+    /// The number of pinned apps at the front of `apps`, 0 while searching.
+    pub pinned: usize,
+    entries: Vec<AppEntry>,
+    /// The indices in `entries` of `apps`.
+    matched: Vec<usize>,
+    /// The number of pinned apps at the front of `entries`.
+    pinned_entries: usize,
+    /// Only apps with windows on this monitor are listed.
+    monitor: Option<HMONITOR>,
+    /// Whether there is an entry for each window rather than for each app.
+    all_windows: bool,
+}
+
+#[derive(Debug)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct WindowList {
+    pub windows: Vec<(HWND, String)>,
+    pub index: usize,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl SwitchAppsState {
+    /// The title of the selected window, or of the selected app's window.
+    pub fn selected_title(&self) -> Option<&str> {
+        if let Some(list) = &self.window_list {
+            return list.windows.get(list.index).map(|(_, v)| v.as_str());
+        }
+        let entry = self.selected_entry()?;
+        entry
+            .windows
+            .iter()
+            .find(|(hwnd, _)| *hwnd == entry.hwnd)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn selected_hwnd(&self) -> Option<HWND> {
+        match &self.window_list {
+            Some(list) => list.windows.get(list.index).map(|(hwnd, _)| *hwnd),
+            None => self.apps.get(self.index).map(|(_, hwnd)| *hwnd),
+        }
+    }
+
+    fn selected_entry(&self) -> Option<&AppEntry> {
+        let i = self.matched.get(self.index)?;
+        self.entries.get(*i)
+    }
+
+    /// Replaces the entries, keeping the query and, where possible, the selection.
+    fn refresh(&mut self, entries: Vec<AppEntry>, pinned_entries: usize) {
+        let key = self.selected_entry().map(|v| v.key.clone());
+        let index = self.index;
+        let list_index = self.window_list.as_ref().map(|v| v.index);
+        self.entries = entries;
+        self.pinned_entries = pinned_entries;
+        self.filter();
+        let position = self
+            .matched
+            .iter()
+            .position(|i| Some(&self.entries[*i].key) == key.as_ref());
+        match position {
+            Some(i) => {
+                self.index = i;
+                if let Some(list_index) = list_index {
+                    self.open_window_list();
+                    if let Some(list) = self.window_list.as_mut() {
+                        list.index = list_index.min(list.windows.len() - 1);
+                    }
+                }
+            }
+            None => self.index = index.min(self.apps.len().saturating_sub(1)),
+        }
+    }
+
+    fn open_window_list(&mut self) {
+        let Some(entry) = self.selected_entry().filter(|v| !v.windows.is_empty()) else {
+            return;
+        };
+        let index = entry
+            .windows
+            .iter()
+            .position(|(hwnd, _)| *hwnd == entry.hwnd)
+            .unwrap_or_default();
+        self.window_list = Some(WindowList {
+            windows: entry.windows.clone(),
+            index,
+        });
+    }
+
+    /// Narrows `apps` down to the entries matching the query and selects the best match.
+    fn filter(&mut self) {
+        let mut matches: Vec<(i32, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| entry.score(&self.query).map(|score| (score, i)))
+            .collect();
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.matched = matches.into_iter().map(|(_, i)| i).collect();
+        self.apps = self
+            .matched
+            .iter()
+            .map(|i| (self.entries[*i].icon, self.entries[*i].hwnd))
+            .collect();
+        // the best matches come first while searching
+        self.pinned = if self.query.is_empty() {
+            self.pinned_entries
+        } else {
+            0
+        };
+        self.index = 0;
+        self.window_list = None;
+    }
+}
+
+/// An app in the switcher along with the texts it can be searched by.
+#[derive(Debug)]
+#[cfg_attr(not(windows), allow(dead_code))]
+struct AppEntry {
+    /// The key from `list_windows`.
+    key: String,
+    icon: HICON,
+    hwnd: HWND,
+    exe: String,
+    product_name: String,
+    windows: Vec<(HWND, String)>,
+    /// What the shell opens to start a pinned app without windows.
+    launch: Option<String>,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl AppEntry {
+    fn score(&self, query: &str) -> Option<i32> {
+        [&self.exe, &self.product_name]
+            .into_iter()
+            .chain(self.windows.iter().map(|(_, title)| title))
+            .filter_map(|text| fuzzy_score(query, text))
+            .max()
+    }
+}
+
+/// `C:\Windows\notepad.exe` => `notepad`
+#[cfg_attr(not(windows), allow(dead_code))]
+fn get_exe_name(module_path: &str) -> String {
+    let name = module_path.rsplit('\\').next().unwrap_or(module_path);
+    match name.len().checked_sub(4) {
+        Some(i)
+            if name
+                .get(i..)
+                .is_some_and(|v| v.eq_ignore_ascii_case(".exe")) =>
+        {
+            name[..i].to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        Hotkey, Launcher, LAUNCHER_HOTKEY_ID, SWITCH_ALL_WINDOWS_HOTKEY_ID, SWITCH_APPS_HOTKEY_ID,
+        SWITCH_WINDOWS_HOTKEY_ID,
+    };
+    use crate::keyboard::{HotkeyStateMachine, KeyAction};
+    use crate::rules::{GroupAction, GroupRule, Rule, RuleAction};
+    use crate::utils::{FakeWindow, FakeWindowSystem};
+
+    const CODE: &str = r"C:\Program Files\Code\Code.exe";
+    const CHROME: &str = r"C:\Program Files\Google\Chrome\Application\chrome.exe";
+    const NOTEPAD: &str = r"C:\Windows\System32\notepad.exe";
+
+    fn switcher(windows: Vec<FakeWindow>) -> Switcher<FakeWindowSystem> {
+        Switcher::new(
+            FakeWindowSystem::new(windows),
+            Config::default(),
+            false,
+            // each test gets its own history
+            Box::leak(Box::default()),
+        )
+    }
+
+    /// Feeds key events through the hotkey state machine into the switcher, like `App` does.
+    fn press(switcher: &mut Switcher<FakeWindowSystem>, events: &[(u32, bool)]) {
+        let switch_windows =
+            Hotkey::create(SWITCH_WINDOWS_HOTKEY_ID, "switch windows", "alt+`").unwrap();
+        let switch_apps = Hotkey::create(SWITCH_APPS_HOTKEY_ID, "switch apps", "alt+tab").unwrap();
+        let switch_all_windows =
+            Hotkey::create(SWITCH_ALL_WINDOWS_HOTKEY_ID, "switch all windows", "alt+q").unwrap();
+        let all_windows_overlay = switcher.config.switch_windows_all_overlay;
+        let launchers = switcher.config.launchers.clone();
+        let mut hotkeys = vec![&switch_windows, &switch_apps, &switch_all_windows];
+        hotkeys.extend(launchers.iter().map(|v| &v.hotkey));
+        let mut machine =
+            HotkeyStateMachine::new(&hotkeys).with_all_windows_overlay(all_windows_overlay);
+        for (scan_code, is_down) in events {
+            for action in machine.handle(*scan_code, *is_down).actions {
+                match action {
+                    KeyAction::SwitchApps { reverse } => switcher.switch_apps(reverse).unwrap(),
+                    KeyAction::SwitchAppsDone => {
+                        switcher.do_switch_app();
+                    }
+                    KeyAction::SwitchAppsCancel => {
+                        switcher.cancel_switch_app();
+                    }
+                    KeyAction::SwitchWindows { reverse } => {
+                        switcher.switch_windows(reverse).unwrap();
+                        switcher.cancel_switch_app();
+                    }
+                    KeyAction::SwitchWindowsDone => switcher.switch_windows_done(),
+                    KeyAction::SwitchAllWindows { reverse } => {
+                        switcher.switch_all_windows(reverse).unwrap();
+                        if !all_windows_overlay {
+                            switcher.cancel_switch_app();
+                        }
+                    }
+                    KeyAction::SwitchAllWindowsDone => {
+                        if all_windows_overlay {
+                            switcher.do_switch_app();
+                        } else {
+                            switcher.switch_windows_done();
+                        }
+                    }
+                    KeyAction::Launch(index) => {
+                        switcher.cancel_switch_app();
+                        switcher.launch(index).unwrap();
+                    }
+                    KeyAction::SearchInput(c) => switcher.search_input(c),
+                    KeyAction::SearchBackspace => switcher.search_backspace(),
+                    KeyAction::Navigate(direction) => switcher.navigate(direction).unwrap(),
+                    KeyAction::Window(action) => switcher.window_action(action).unwrap(),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_hotkey_pipeline() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, CHROME, "Google"),
+            FakeWindow::new(3, NOTEPAD, "todo.txt"),
+            FakeWindow::new(4, CODE, "b.rs"),
+        ]);
+        // alt+tab, tab
+        press(
+            &mut switcher,
+            &[
+                (0x38, true),
+                (0x0f, true),
+                (0x0f, false),
+                (0x0f, true),
+                (0x0f, false),
+                (0x38, false),
+            ],
+        );
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+        // alt+tab, escape
+        press(
+            &mut switcher,
+            &[(0x38, true), (0x0f, true), (0x01, true), (0x38, false)],
+        );
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+        // alt+tab back to code, then alt+` to its other window
+        press(&mut switcher, &[(0x38, true), (0x0f, true), (0x38, false)]);
+        assert_eq!(switcher.ws.foreground(), HWND(1 as _));
+        press(&mut switcher, &[(0x38, true), (0x29, true), (0x38, false)]);
+        assert_eq!(switcher.ws.foreground(), HWND(4 as _));
+    }
+
+    #[test]
+    fn test_switch_windows_cycles_same_app() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, NOTEPAD, "todo.txt"),
+            FakeWindow::new(3, CODE, "b.rs"),
+            FakeWindow::new(4, CODE, "c.rs"),
+        ]);
+        assert!(switcher.switch_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+        assert!(switcher.switch_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(4 as _));
+        assert!(switcher.switch_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(1 as _));
+        assert!(switcher.switch_windows(true).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(4 as _));
+
+        // Releasing the modifier and pressing again goes back to the previous window
+        switcher.switch_windows_done();
+        assert!(switcher.switch_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(1 as _));
+    }
+
+    #[test]
+    fn test_switch_windows_single_window() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, NOTEPAD, "todo.txt"),
+            FakeWindow::new(2, CODE, "a.rs"),
+        ]);
+        assert!(!switcher.switch_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(1 as _));
+    }
+
+    #[test]
+    fn test_switch_all_windows() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, NOTEPAD, "todo.txt"),
+            FakeWindow::new(3, CODE, "b.rs"),
+            FakeWindow::new(4, CHROME, "Google"),
+        ]);
+        for id in [4, 3, 2, 1] {
+            switcher.mru.lock().touch(HWND(id as _));
+        }
+        // every window in MRU order, whatever its app
+        assert!(switcher.switch_all_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(2 as _));
+        assert!(switcher.switch_all_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+        assert!(switcher.switch_all_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(4 as _));
+        assert!(switcher.switch_all_windows(true).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+
+        // Releasing the modifier and pressing again goes back to the previous window
+        switcher.switch_windows_done();
+        assert!(switcher.switch_all_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(1 as _));
+    }
+
+    #[test]
+    fn test_launch() {
+        const WIN: u32 = 0xe05b;
+        const KEY_1: u32 = 0x02;
+        const KEY_2: u32 = 0x03;
+        const TERMINAL: &str = "Microsoft.WindowsTerminal_8wekyb3d8bbwe!App";
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, NOTEPAD, "todo.txt"),
+            FakeWindow::new(2, CODE, "a.rs"),
+            FakeWindow::new(3, CHROME, "Google"),
+            FakeWindow::new(4, CODE, "b.rs").iconic(true),
+            FakeWindow::new(5, CODE, "c.rs"),
+        ]);
+        switcher.config.launchers = vec![
+            Launcher::parse(LAUNCHER_HOTKEY_ID, "win+1", "code.exe").unwrap(),
+            Launcher::parse(LAUNCHER_HOTKEY_ID + 1, "win+2", TERMINAL).unwrap(),
+        ];
+        for id in [5, 3, 4, 2, 1] {
+            switcher.mru.lock().touch(HWND(id as _));
+        }
+
+        // focuses the most recent window, then cycles the windows of the app while win is held
+        press(&mut switcher, &[(WIN, true), (KEY_1, true), (KEY_1, false)]);
+        assert_eq!(switcher.ws.foreground(), HWND(2 as _));
+        switcher.launch(0).unwrap();
+        assert_eq!(switcher.ws.foreground(), HWND(4 as _));
+        switcher.launch(0).unwrap();
+        assert_eq!(switcher.ws.foreground(), HWND(5 as _));
+        // releasing win and pressing again goes back to the first window, like switching windows
+        switcher.switch_windows_done();
+        switcher.launch(0).unwrap();
+        assert_eq!(switcher.ws.foreground(), HWND(2 as _));
+        assert!(switcher.ws.launched().is_empty());
+
+        // nothing is running, so the app is started
+        press(
+            &mut switcher,
+            &[(WIN, true), (KEY_2, true), (KEY_2, false), (WIN, false)],
+        );
+        assert_eq!(
+            switcher.ws.launched(),
+            [format!(r"shell:AppsFolder\{TERMINAL}")]
+        );
+        assert_eq!(switcher.ws.foreground(), HWND(2 as _));
+    }
+
+    #[test]
+    fn test_launch_targets() {
+        const TERMINAL: &str = "Microsoft.WindowsTerminal_8wekyb3d8bbwe!App";
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, NOTEPAD, "todo.txt"),
+            FakeWindow::new(2, CHROME, "Terminal").aumid(TERMINAL),
+        ]);
+        switcher.config.launchers = vec![
+            Launcher::parse(LAUNCHER_HOTKEY_ID, "win+1", CODE).unwrap(),
+            Launcher::parse(LAUNCHER_HOTKEY_ID + 1, "win+2", TERMINAL).unwrap(),
+        ];
+        switcher.launch(1).unwrap();
+        assert_eq!(switcher.ws.foreground(), HWND(2 as _));
+        switcher.launch(0).unwrap();
+        assert_eq!(switcher.ws.launched(), [CODE]);
+    }
+
+    #[test]
+    fn test_launch_other_desktop() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, NOTEPAD, "todo.txt"),
+            // windows on other virtual desktops are cloaked by the shell
+            FakeWindow::new(2, CODE, "a.rs").cloaked(2).monitor(2),
+        ]);
+        let text = "[switch-windows]\nonly_current_desktop = yes\nonly_current_monitor = yes\n\
+                    [launchers]\nwin+1 = code.exe\n";
+        switcher.config = Config::load(&ini::Ini::load_from_str(text).unwrap()).unwrap();
+        switcher.launch(0).unwrap();
+        assert_eq!(switcher.ws.foreground(), HWND(2 as _));
+        assert!(switcher.ws.launched().is_empty());
+    }
+
+    #[test]
+    fn test_switch_all_windows_overlay() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, NOTEPAD, "todo.txt"),
+            FakeWindow::new(3, CODE, "b.rs"),
+        ]);
+        switcher.config.switch_windows_all_overlay = true;
+        for id in [3, 2, 1] {
+            switcher.mru.lock().touch(HWND(id as _));
+        }
+        assert!(switcher.switch_all_windows(false).unwrap());
+        let state = switcher.switch_apps_state().unwrap();
+        let hwnds: Vec<HWND> = state.apps.iter().map(|(_, hwnd)| *hwnd).collect();
+        assert_eq!(hwnds, [HWND(1 as _), HWND(2 as _), HWND(3 as _)]);
+        assert_eq!(state.index, 1);
+        assert!(state.show_title);
+
+        // alt+q, q, release
+        press(
+            &mut switcher,
+            &[(0x38, true), (0x10, true), (0x10, false), (0x38, false)],
+        );
+        assert!(switcher.switch_apps_state().is_none());
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+    }
+
+    #[test]
+    fn test_switch_apps() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, CHROME, "Google"),
+            FakeWindow::new(3, CODE, "b.rs"),
+            FakeWindow::new(4, NOTEPAD, "todo.txt"),
+        ]);
+        switcher.switch_apps(false).unwrap();
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!(state.apps.len(), 3);
+        assert_eq!(state.index, 1);
+        switcher.switch_apps(false).unwrap();
+        switcher.switch_apps(false).unwrap();
+        assert_eq!(switcher.switch_apps_state().unwrap().index, 0);
+        switcher.switch_apps(true).unwrap();
+        assert!(switcher.do_switch_app().is_some());
+        assert_eq!(switcher.ws.foreground(), HWND(4 as _));
+        assert!(switcher.switch_apps_state().is_none());
+    }
+
+    #[test]
+    fn test_pinned_apps() {
+        const TERMINAL: &str = "Microsoft.WindowsTerminal_8wekyb3d8bbwe!App";
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, CHROME, "Google"),
+            FakeWindow::new(3, NOTEPAD, "todo.txt"),
+        ]);
+        switcher.config.switch_apps_pinned = ["notepad.exe", TERMINAL, CHROME]
+            .into_iter()
+            .filter_map(Program::parse)
+            .collect();
+        let hwnds = |switcher: &Switcher<FakeWindowSystem>| -> Vec<isize> {
+            let state = switcher.switch_apps_state().unwrap();
+            state.apps.iter().map(|(_, hwnd)| hwnd.0 as isize).collect()
+        };
+
+        // the previous app is still selected, wherever it is pinned
+        switcher.switch_apps(false).unwrap();
+        assert_eq!(hwnds(&switcher), [3, 2, 1]);
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!((state.index, state.pinned), (1, 2));
+        // the separator is hidden while searching
+        switcher.search_input('t');
+        assert_eq!(hwnds(&switcher), [3]);
+        assert_eq!(switcher.switch_apps_state().unwrap().pinned, 0);
+        switcher.search_backspace();
+        assert_eq!(switcher.switch_apps_state().unwrap().pinned, 2);
+        switcher.cancel_switch_app();
+
+        // pinned apps without windows are started when selected
+        switcher.config.switch_apps_pinned_closed = true;
+        switcher.switch_apps(false).unwrap();
+        assert_eq!(hwnds(&switcher), [3, 0, 2, 1]);
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!((state.index, state.pinned), (2, 3));
+        switcher.navigate(Direction::Left).unwrap();
+        assert_eq!(switcher.switch_apps_state().unwrap().selected_title(), None);
+        switcher.window_action(WindowAction::Kill).unwrap();
+        assert_eq!(hwnds(&switcher), [3, 0, 2, 1]);
+        assert!(switcher.do_switch_app().is_some());
+        assert_eq!(
+            switcher.ws.launched(),
+            [format!(r"shell:AppsFolder\{TERMINAL}")]
+        );
+        assert_eq!(switcher.ws.foreground(), HWND(1 as _));
+    }
+
+    #[test]
+    fn test_pinned_apps_without_windows() {
+        let mut switcher = switcher(vec![]);
+        switcher.config.switch_apps_pinned = vec![Program::parse(NOTEPAD).unwrap()];
+        switcher.switch_apps(false).unwrap();
+        assert!(switcher.switch_apps_state().is_none());
+
+        switcher.config.switch_apps_pinned_closed = true;
+        switcher.switch_apps(false).unwrap();
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!((state.apps.len(), state.index, state.pinned), (1, 0, 1));
+        assert!(switcher.do_switch_app().is_some());
+        assert_eq!(switcher.ws.launched(), [NOTEPAD]);
+    }
+
+    #[test]
+    fn test_switch_apps_mru_order() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, CHROME, "Google"),
+            FakeWindow::new(3, CODE, "b.rs"),
+            FakeWindow::new(4, NOTEPAD, "todo.txt"),
+        ]);
+        // The Z-order disagrees with the activation order, e.g. after a window raised itself.
+        for id in [2, 3, 1, 4, 1] {
+            switcher.mru.lock().touch(HWND(id as _));
+        }
+        let windows = switcher.list_windows(false, false, None).unwrap();
+        let order: Vec<Vec<isize>> = windows
+            .values()
+            .map(|v| v.iter().map(|(hwnd, _)| hwnd.0 as isize).collect())
+            .collect();
+        assert_eq!(order, [vec![1, 3], vec![4], vec![2]]);
+
+        switcher.switch_apps(false).unwrap();
+        assert!(switcher.do_switch_app().is_some());
+        assert_eq!(switcher.ws.foreground(), HWND(4 as _));
+        assert_eq!(switcher.mru.lock().rank(HWND(4 as _)), 0);
+    }
+
+    #[test]
+    fn test_search_apps() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "main.rs").product_name("Visual Studio Code"),
+            FakeWindow::new(2, CHROME, "Google").product_name("Google Chrome"),
+            FakeWindow::new(3, NOTEPAD, "todo.txt").product_name("Notepad"),
+        ]);
+        switcher.switch_apps(false).unwrap();
+        assert_eq!(switcher.switch_apps_state().unwrap().apps.len(), 3);
+
+        // by product name
+        for c in "vsc".chars() {
+            switcher.search_input(c);
+        }
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!(state.query, "vsc");
+        assert_eq!(
+            state.apps.iter().map(|v| v.1).collect::<Vec<_>>(),
+            [HWND(1 as _)]
+        );
+        assert_eq!(state.index, 0);
+
+        // nothing matches, cycling and switching do nothing
+        switcher.search_input('z');
+        assert!(switcher.switch_apps_state().unwrap().apps.is_empty());
+        switcher.switch_apps(false).unwrap();
+
+        // by exe name and window title
+        for _ in 0..4 {
+            switcher.search_backspace();
+        }
+        switcher.search_backspace();
+        assert_eq!(switcher.switch_apps_state().unwrap().apps.len(), 3);
+        switcher.search_input('t');
+        switcher.search_input('o');
+        switcher.search_input('d');
+        assert_eq!(
+            switcher.switch_apps_state().unwrap().apps[0].1,
+            HWND(3 as _)
+        );
+        assert!(switcher.do_switch_app().is_some());
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+    }
+
+    #[test]
+    fn test_window_list() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, NOTEPAD, "todo.txt"),
+            FakeWindow::new(2, CODE, "a.rs"),
+            FakeWindow::new(3, CODE, "b.rs"),
+            FakeWindow::new(4, CODE, "c.rs"),
+        ]);
+        let mut config = Config::default();
+        config.switch_apps_window_list = true;
+        switcher.set_config(config);
+
+        switcher.switch_apps(false).unwrap();
+        let state = switcher.switch_apps_state().unwrap();
+        assert!(state.show_title);
+        assert_eq!(state.selected_title(), Some("a.rs"));
+
+        switcher.navigate(Direction::Down).unwrap();
+        switcher.navigate(Direction::Down).unwrap();
+        switcher.navigate(Direction::Down).unwrap();
+        switcher.navigate(Direction::Down).unwrap();
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!(state.window_list.as_ref().unwrap().windows.len(), 3);
+        assert_eq!(state.selected_title(), Some("c.rs"));
+
+        // Up on the first window closes the list, Left moves to the previous app
+        switcher.navigate(Direction::Up).unwrap();
+        switcher.navigate(Direction::Up).unwrap();
+        switcher.navigate(Direction::Up).unwrap();
+        assert!(switcher.switch_apps_state().unwrap().window_list.is_none());
+        switcher.navigate(Direction::Left).unwrap();
+        assert_eq!(switcher.switch_apps_state().unwrap().index, 0);
+
+        switcher.navigate(Direction::Right).unwrap();
+        switcher.navigate(Direction::Down).unwrap();
+        switcher.select_window(1);
+        assert!(switcher.do_switch_app().is_some());
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+    }
+
+    #[test]
+    fn test_grid_navigation() {
+        let mut switcher = switcher(
+            (1..=7)
+                .map(|i| FakeWindow::new(i, &format!(r"C:\apps\{i}.exe"), "app"))
+                .collect(),
+        );
+        switcher.switch_apps(false).unwrap();
+        switcher.set_columns(3);
+        let index =
+            |switcher: &Switcher<FakeWindowSystem>| switcher.switch_apps_state().unwrap().index;
+        assert_eq!(index(&switcher), 1);
+
+        // 0 1 2
+        // 3 4 5
+        // 6
+        switcher.navigate(Direction::Down).unwrap();
+        assert_eq!(index(&switcher), 4);
+        switcher.navigate(Direction::Down).unwrap();
+        assert_eq!(index(&switcher), 6);
+        // the bottom row stays put without a window list
+        switcher.navigate(Direction::Down).unwrap();
+        assert_eq!(index(&switcher), 6);
+        switcher.navigate(Direction::Up).unwrap();
+        switcher.navigate(Direction::Up).unwrap();
+        assert_eq!(index(&switcher), 0);
+        switcher.navigate(Direction::Up).unwrap();
+        assert_eq!(index(&switcher), 0);
+        switcher.navigate(Direction::Left).unwrap();
+        assert_eq!(index(&switcher), 6);
+
+        // a single row cycles on Up/Down
+        switcher.set_columns(10);
+        switcher.navigate(Direction::Down).unwrap();
+        assert_eq!(index(&switcher), 0);
+        switcher.navigate(Direction::Up).unwrap();
+        assert_eq!(index(&switcher), 6);
+    }
+
+    #[test]
+    fn test_window_actions() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, NOTEPAD, "todo.txt"),
+            FakeWindow::new(2, CODE, "a.rs"),
+            FakeWindow::new(3, CHROME, "Google"),
+            FakeWindow::new(4, CODE, "b.rs"),
+            FakeWindow::new(5, CODE, "c.rs"),
+        ]);
+        let mut config = Config::default();
+        config.switch_apps_window_list = true;
+        switcher.set_config(config);
+        switcher.switch_apps(false).unwrap();
+
+        // close the selected window of the list, the list stays open
+        switcher.navigate(Direction::Down).unwrap();
+        switcher.navigate(Direction::Down).unwrap();
+        switcher.window_action(WindowAction::CloseWindow).unwrap();
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!(state.selected_title(), Some("c.rs"));
+        assert_eq!(state.window_list.as_ref().unwrap().windows.len(), 2);
+
+        // minimize a window of the list
+        switcher.window_action(WindowAction::Minimize).unwrap();
+        assert!(switcher.ws.is_iconic_window(HWND(5 as _)));
+        assert!(!switcher.ws.is_iconic_window(HWND(2 as _)));
+
+        // close the whole app, the next one takes its place
+        switcher.window_action(WindowAction::CloseApp).unwrap();
+        let state = switcher.switch_apps_state().unwrap();
+        assert_eq!(state.apps.len(), 2);
+        assert_eq!(state.index, 1);
+        assert!(state.window_list.is_none());
+        assert_eq!(state.selected_title(), Some("Google"));
+
+        switcher.window_action(WindowAction::Kill).unwrap();
+        assert_eq!(switcher.switch_apps_state().unwrap().apps.len(), 1);
+        assert_eq!(switcher.ws.enum_windows().unwrap(), [HWND(1 as _)]);
+    }
+
+    #[test]
+    fn test_kill_protected_process() {
+        const EXPLORER: &str = r"C:\Windows\explorer.exe";
+        const FRAME_HOST: &str = r"C:\Windows\System32\ApplicationFrameHost.exe";
+        const CALCULATOR: &str = r"C:\Program Files\WindowsApps\Calculator\CalculatorApp.exe";
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, EXPLORER, "Downloads"),
+            FakeWindow::new(3, EXPLORER, "Documents"),
+            FakeWindow::new(4, FRAME_HOST, "Calculator"),
+            FakeWindow::new(5, CALCULATOR, "Calculator")
+                .visible(false)
+                .owner(4),
+            FakeWindow::new(6, FRAME_HOST, "Settings"),
+            FakeWindow::new(7, NOTEPAD, "switcher").pid(std::process::id()),
+        ]);
+        let select = |switcher: &mut Switcher<FakeWindowSystem>, hwnd: isize| {
+            let state = switcher.switch_apps_state().unwrap();
+            let index = state.apps.iter().position(|(_, v)| *v == HWND(hwnd as _));
+            switcher.select_app(index.unwrap());
+        };
+        let windows = |switcher: &Switcher<FakeWindowSystem>| -> Vec<isize> {
+            let hwnds = switcher.ws.enum_windows().unwrap();
+            hwnds.iter().map(|v| v.0 as isize).collect()
+        };
+        switcher.switch_apps(false).unwrap();
+
+        // only the selected window of the shell is closed
+        select(&mut switcher, 2);
+        switcher.window_action(WindowAction::Kill).unwrap();
+        assert_eq!(windows(&switcher), [1, 3, 4, 5, 6, 7]);
+        // the host of the UWP app keeps the other apps
+        select(&mut switcher, 4);
+        switcher.window_action(WindowAction::Kill).unwrap();
+        assert_eq!(windows(&switcher), [1, 3, 5, 6, 7]);
+        // the switcher doesn't kill itself
+        select(&mut switcher, 7);
+        switcher.window_action(WindowAction::Kill).unwrap();
+        assert_eq!(windows(&switcher), [1, 3, 5, 6]);
+
+        // other processes are killed
+        select(&mut switcher, 1);
+        switcher.window_action(WindowAction::Kill).unwrap();
+        assert_eq!(windows(&switcher), [3, 5, 6]);
+    }
+
+    #[test]
+    fn test_get_exe_name() {
+        assert_eq!(get_exe_name(NOTEPAD), "notepad");
+        assert_eq!(get_exe_name(r"C:\apps\Tool.EXE"), "Tool");
+        assert_eq!(get_exe_name("app"), "app");
+    }
+
+    #[test]
+    fn test_cancel_switch_apps() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, NOTEPAD, "todo.txt"),
+        ]);
+        switcher.switch_apps(false).unwrap();
+        assert!(switcher.cancel_switch_app().is_some());
+        assert!(switcher.do_switch_app().is_none());
+        assert_eq!(switcher.ws.foreground(), HWND(1 as _));
+    }
+
+    #[test]
+    fn test_only_current_monitor() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs").monitor(2),
+            FakeWindow::new(2, CODE, "b.rs"),
+            FakeWindow::new(3, CODE, "c.rs").monitor(2),
+            FakeWindow::new(4, NOTEPAD, "todo.txt"),
+            FakeWindow::new(5, CHROME, "Google").monitor(2),
+        ]);
+        let mut config = Config::default();
+        config.switch_windows_only_current_monitor = true;
+        config.switch_apps_only_current_monitor = true;
+        switcher.set_config(config);
+
+        // b.rs is on the other monitor
+        assert!(switcher.switch_windows(false).unwrap());
+        assert_eq!(switcher.ws.foreground(), HWND(3 as _));
+        switcher.switch_windows_done();
+
+        switcher.switch_apps(false).unwrap();
+        let state = switcher.switch_apps_state().unwrap();
+        let hwnds: Vec<HWND> = state.apps.iter().map(|(_, hwnd)| *hwnd).collect();
+        assert_eq!(hwnds, [HWND(3 as _), HWND(5 as _)]);
+        // refreshing after an action keeps the filter
+        switcher.window_action(WindowAction::Minimize).unwrap();
+        assert_eq!(switcher.switch_apps_state().unwrap().apps.len(), 2);
+    }
+
+    #[test]
+    fn test_list_windows_filters() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, NOTEPAD, "hidden").visible(false),
+            FakeWindow::new(3, NOTEPAD, "tool").tool(true),
+            FakeWindow::new(4, NOTEPAD, "topmost").topmost(true),
+            FakeWindow::new(5, NOTEPAD, ""),
+            FakeWindow::new(8, NOTEPAD, "small").small(true),
+            FakeWindow::new(6, NOTEPAD, "min").iconic(true),
+            FakeWindow::new(7, r"C:\Windows\System32\Taskmgr.exe", "admin").elevated(true),
+        ]);
+        let windows = switcher.list_windows(false, false, None).unwrap();
+        let titles: Vec<&str> = windows
+            .values()
+            .flatten()
+            .map(|(_, title)| title.as_str())
+            .collect();
+        assert_eq!(titles, ["a.rs", "min"]);
+        let windows = switcher.list_windows(true, false, None).unwrap();
+        assert_eq!(windows.len(), 1);
+    }
+
+    #[test]
+    fn test_list_windows_rules() {
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CODE, "a.rs"),
+            FakeWindow::new(2, CHROME, "Chat").topmost(true),
+            FakeWindow::new(3, NOTEPAD, "a.txt"),
+            FakeWindow::new(4, CODE, "Settings").class("SettingsWindow"),
+            FakeWindow::new(5, NOTEPAD, "topmost").topmost(true),
+        ]);
+        let mut config = Config::default();
+        config.rules = vec![
+            Rule::parse(RuleAction::Include, "exe=chrome.exe; style=topmost").unwrap(),
+            Rule::parse(RuleAction::Exclude, r"exe=*\\System32\\*").unwrap(),
+            Rule::parse(RuleAction::Exclude, "class=settings*").unwrap(),
+        ];
+        switcher.set_config(config);
+        let windows = switcher.list_windows(false, false, None).unwrap();
+        let titles: Vec<&str> = windows
+            .values()
+            .flatten()
+            .map(|(_, title)| title.as_str())
+            .collect();
+        assert_eq!(titles, ["a.rs", "Chat"]);
+    }
+
+    #[test]
+    fn test_list_windows_groups() {
+        const TERMINAL: &str = r"C:\Program Files\WindowsApps\Terminal\WindowsTerminal.exe";
+        const CONSOLE: &str = r"C:\Program Files\WindowsApps\Terminal\OpenConsole.exe";
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, CONSOLE, "cmd"),
+            FakeWindow::new(2, CODE, "a.rs - Code"),
+            FakeWindow::new(3, TERMINAL, "pwsh"),
+            FakeWindow::new(4, CODE, "b.rs - Code"),
+        ]);
+        let mut config = Config::default();
+        config.groups = vec![
+            GroupRule::parse(GroupAction::Merge, r"exe=*\\Terminal\\*.exe").unwrap(),
+            GroupRule::parse(GroupAction::Split, r"exe=code.exe; title=^(.+) - Code$").unwrap(),
+        ];
+        switcher.set_config(config);
+        let windows = switcher.list_windows(false, false, None).unwrap();
+        let keys: Vec<String> = windows.keys().cloned().collect();
+        assert_eq!(
+            keys,
+            [
+                "::group::0".to_string(),
+                format!("{CODE}::title::a.rs"),
+                format!("{CODE}::title::b.rs"),
+            ]
+        );
+        assert_eq!(windows["::group::0"].len(), 2);
+
+        // the key stays the same when another member comes first
+        switcher.mru.lock().touch(HWND(3 as _));
+        let windows = switcher.list_windows(false, false, None).unwrap();
+        assert_eq!(windows.keys().next().unwrap(), "::group::0");
+        assert_eq!(windows["::group::0"][0].0, HWND(3 as _));
+        assert_eq!(switcher.member_keys("::group::0"), [CONSOLE, TERMINAL]);
+        let entries = switcher.app_entries(&[], None).unwrap();
+        assert_eq!(entries[0].exe, "OpenConsole");
+    }
+
+    #[test]
+    fn test_list_windows_by_aumid() {
+        const JAVA: &str = r"C:\Program Files\Java\bin\javaw.exe";
+        let mut switcher = switcher(vec![
+            FakeWindow::new(1, JAVA, "IDE").aumid("com.ide"),
+            FakeWindow::new(2, JAVA, "Game").aumid("com.game"),
+            FakeWindow::new(3, JAVA, "Tool"),
+            FakeWindow::new(4, CHROME, "Profile 1").aumid("Chrome.UserData.Profile1"),
+        ]);
+        let conf = ini::Ini::load_from_str("[groups]\nby_aumid = yes\n").unwrap();
+        switcher.set_config(Config::load(&conf).unwrap());
+        let windows = switcher.list_windows(false, false, None).unwrap();
+        let keys: Vec<String> = windows.keys().cloned().collect();
+        assert_eq!(
+            keys,
+            [
+                format!("{JAVA}::aumid::com.ide"),
+                format!("{JAVA}::aumid::com.game"),
+                JAVA.to_string(),
+                format!("{CHROME}::Profile1"),
+            ]
+        );
+    }
+}
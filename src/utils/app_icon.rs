@@ -1,4 +1,4 @@
-use super::to_wstring;
+use super::{find_browser, to_wstring};
 
 use std::{
     fs::File,
@@ -18,10 +18,14 @@ use windows::{
             SelectObject, BITMAP, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS, HBITMAP, HDC,
             HGDIOBJ, RGBQUAD,
         },
-        Storage::FileSystem::FILE_ATTRIBUTE_NORMAL,
+        Storage::FileSystem::{FILE_ATTRIBUTE_NORMAL, FILE_FLAGS_AND_ATTRIBUTES},
+        System::Com::CoTaskMemFree,
         UI::{
             Controls::IImageList,
-            Shell::{SHGetFileInfoW, SHGetImageList, SHFILEINFOW, SHGFI_SYSICONINDEX},
+            Shell::{
+                Common::ITEMIDLIST, SHGetFileInfoW, SHGetImageList, SHParseDisplayName,
+                SHFILEINFOW, SHGFI_PIDL, SHGFI_SYSICONINDEX,
+            },
             WindowsAndMessaging::{
                 CopyIcon, CreateIconFromResourceEx, DestroyIcon, GetIconInfo, LoadIconW,
                 LoadImageW, SendMessageTimeoutW, GCLP_HICON, HICON, ICONINFO, ICON_BIG,
@@ -55,6 +59,13 @@ pub fn get_app_icon(
         }
     }
 
+    if let Some(aumid) = parse_aumid_key(module_path) {
+        // the exe is often a shared host such as javaw.exe, prefer the icon of the window
+        return get_aumid_icon(aumid)
+            .or_else(|| get_window_icon(hwnd))
+            .unwrap_or_else(fallback_icon);
+    }
+
     if let Some(icon) = get_pwa_icon_from_lnk(module_path) {
         return icon;
     }
@@ -230,49 +241,24 @@ pub fn get_window_icon(hwnd: HWND) -> Option<HICON> {
 }
 
 fn get_browser_profile_icon(module_path: &str) -> Option<HICON> {
-    let parts: Vec<&str> = module_path.split("::").collect();
-    if parts.len() != 2 {
-        return None;
-    }
-    let exe_path = parts[0];
-    let profile = parts[1];
-
-    let local_app_data = std::env::var("LOCALAPPDATA").ok()?;
-    let (user_data_dir, icon_file) = if exe_path.to_lowercase().contains("chrome.exe") {
-        (
-            PathBuf::from(&local_app_data).join(r"Google\Chrome\User Data"),
-            "Google Profile.ico",
-        )
-    } else if exe_path.to_lowercase().contains("msedge.exe") {
-        (
-            PathBuf::from(&local_app_data).join(r"Microsoft\Edge\User Data"),
-            "Edge Profile.ico",
-        )
-    } else {
+    let [exe_path, profile] = module_path.split("::").collect::<Vec<_>>()[..] else {
         return None;
     };
-
-    let profile_dir = super::window::pwa_map_profile_dir(profile);
-    let icon_path = user_data_dir.join(&profile_dir).join(icon_file);
+    let icon_path = find_browser(exe_path)?.profile_icon_path(profile)?;
     load_image_as_hicon(&icon_path)
 }
 
 fn get_pwa_icon_from_lnk(module_path: &str) -> Option<HICON> {
-    let parts: Vec<&str> = module_path.split("::").collect();
-    if parts.len() != 3 {
+    let [exe_path, typ, app_id] = module_path.split("::").collect::<Vec<_>>()[..] else {
         return None;
-    }
-    let exe_path = parts[0];
-    let typ = parts[1];
-    let app_id = parts[2];
+    };
 
     if typ == "appx" {
         let package_dir = super::window::find_appx_pkg_dir(app_id)?;
         let logo_path = get_appx_logo_from_dir(&PathBuf::from(package_dir))?;
         load_image_as_hicon(&logo_path)
     } else {
-        let user_data_dir = super::window::get_default_user_data_dir(exe_path)?;
-        let lnk_path = super::window::pwa_find_lnk_path(&user_data_dir, typ, app_id)?;
+        let lnk_path = find_browser(exe_path)?.find_web_app_lnk(typ, app_id)?;
         get_exe_icon(&lnk_path.to_string_lossy())
     }
 }
@@ -281,14 +267,50 @@ const SHIL_JUMBO: i32 = 0x04;
 const SHIL_EXTRALARGE: i32 = 0x02;
 const SHIL_LARGE: i32 = 0x00;
 
+/// Returns the AppUserModelID of apps keyed by `<module_path>::aumid::<aumid>`.
+fn parse_aumid_key(module_path: &str) -> Option<&str> {
+    match module_path.split("::").collect::<Vec<_>>()[..] {
+        [_, "aumid", aumid] if !aumid.is_empty() => Some(aumid),
+        _ => None,
+    }
+}
+
+/// Looks up the icon of the AppUserModelID in the AppsFolder, where the shortcuts
+/// of the start menu and the installed packages are listed.
+fn get_aumid_icon(aumid: &str) -> Option<HICON> {
+    let name = to_wstring(&format!(r"shell:AppsFolder\{aumid}"));
+    let mut file_info = SHFILEINFOW::default();
+    unsafe {
+        let mut pidl: *mut ITEMIDLIST = std::ptr::null_mut();
+        SHParseDisplayName(PCWSTR(name.as_ptr()), None, &mut pidl, 0, None).ok()?;
+        let ret = SHGetFileInfoW(
+            PCWSTR(pidl as *const u16),
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut file_info),
+            mem::size_of_val(&file_info) as u32,
+            SHGFI_PIDL | SHGFI_SYSICONINDEX,
+        );
+        CoTaskMemFree(Some(pidl as _));
+        if ret == 0 {
+            return None;
+        }
+    }
+    get_system_icon(file_info.iIcon)
+}
+
 fn get_exe_icon(module_path: &str) -> Option<HICON> {
     let info = get_shfileinfo(module_path)?;
+    get_system_icon(info.iIcon)
+}
+
+/// Picks the largest valid icon of the system image list.
+fn get_system_icon(index: i32) -> Option<HICON> {
     for shil in [SHIL_JUMBO, SHIL_EXTRALARGE, SHIL_LARGE] {
         unsafe {
             let Some(list) = SHGetImageList::<IImageList>(shil).ok() else {
                 continue;
             };
-            let Some(hicon) = list.GetIcon(info.iIcon, 1u32).ok() else {
+            let Some(hicon) = list.GetIcon(index, 1u32).ok() else {
                 continue;
             };
             match is_valid_icon(hicon) {
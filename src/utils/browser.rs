@@ -0,0 +1,315 @@
+use std::path::PathBuf;
+
+/// A browser whose windows are told apart by profile and installed web app.
+#[derive(Debug)]
+pub struct Browser {
+    /// The lowercase file name of the executable.
+    pub exe: &'static str,
+    /// The AUMID of the default profile, other profiles append `.UserData.<profile>`.
+    aumid: &'static str,
+    /// The user data folder relative to `%LOCALAPPDATA%`.
+    user_data_dir: Option<&'static str>,
+    /// The icon file in each profile folder.
+    profile_icon: Option<&'static str>,
+    kind: BrowserKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BrowserKind {
+    Chromium,
+    Firefox,
+}
+
+const BROWSERS: &[Browser] = &[
+    Browser {
+        exe: "chrome.exe",
+        aumid: "Chrome",
+        user_data_dir: Some(r"Google\Chrome\User Data"),
+        profile_icon: Some("Google Profile.ico"),
+        kind: BrowserKind::Chromium,
+    },
+    Browser {
+        exe: "msedge.exe",
+        aumid: "MSEdge",
+        user_data_dir: Some(r"Microsoft\Edge\User Data"),
+        profile_icon: Some("Edge Profile.ico"),
+        kind: BrowserKind::Chromium,
+    },
+    Browser {
+        exe: "brave.exe",
+        aumid: "Brave",
+        user_data_dir: Some(r"BraveSoftware\Brave-Browser\User Data"),
+        profile_icon: Some("Google Profile.ico"),
+        kind: BrowserKind::Chromium,
+    },
+    Browser {
+        exe: "vivaldi.exe",
+        aumid: "Vivaldi",
+        user_data_dir: Some(r"Vivaldi\User Data"),
+        profile_icon: Some("Google Profile.ico"),
+        kind: BrowserKind::Chromium,
+    },
+    Browser {
+        exe: "firefox.exe",
+        // a hash of the install folder
+        aumid: "",
+        // profiles live in `%APPDATA%` and have no icon of their own
+        user_data_dir: None,
+        profile_icon: None,
+        kind: BrowserKind::Firefox,
+    },
+];
+
+/// What the AUMID of a browser window tells apart.
+#[derive(Debug, PartialEq, Eq)]
+enum BrowserApp<'a> {
+    Default,
+    Profile(&'a str),
+    /// An installed web app of a profile.
+    WebApp {
+        profile: &'a str,
+        app_id: &'a str,
+    },
+    /// A web app packaged as an appx, by its package family name.
+    Appx(&'a str),
+    /// A Firefox taskbar tab, registered with its own AUMID.
+    TaskbarTab(&'a str),
+}
+
+/// Finds the browser of the module path by the name of the executable.
+pub fn find_browser(module_path: &str) -> Option<&'static Browser> {
+    let name = module_path.rsplit('\\').next()?.to_lowercase();
+    BROWSERS.iter().find(|v| v.exe == name)
+}
+
+impl Browser {
+    /// Returns the key of the app of a window of the browser, keyed by
+    /// `<module_path>::<profile>` for profiles, `<module_path>::<profile>::<app_id>` for web apps,
+    /// `<module_path>::appx::<package>` for packaged web apps and
+    /// `<module_path>::aumid::<aumid>` for taskbar tabs.
+    pub fn app_key(&self, module_path: &str, aumid: &str) -> String {
+        let app = match self.kind {
+            BrowserKind::Chromium => parse_chromium_aumid(self.aumid, aumid),
+            BrowserKind::Firefox => parse_firefox_aumid(aumid),
+        };
+        match app {
+            BrowserApp::Default => module_path.to_string(),
+            BrowserApp::Profile(profile) => format!("{module_path}::{profile}"),
+            BrowserApp::WebApp { profile, app_id } => {
+                format!("{module_path}::{profile}::{app_id}")
+            }
+            BrowserApp::Appx(package) => format!("{module_path}::appx::{package}"),
+            BrowserApp::TaskbarTab(aumid) => format!("{module_path}::aumid::{aumid}"),
+        }
+    }
+
+    pub fn user_data_dir(&self) -> Option<PathBuf> {
+        let local_app_data = std::env::var("LOCALAPPDATA").ok()?;
+        Some(PathBuf::from(local_app_data).join(self.user_data_dir?))
+    }
+
+    pub fn profile_icon_path(&self, profile: &str) -> Option<PathBuf> {
+        let profile_dir = pwa_map_profile_dir(profile);
+        Some(
+            self.user_data_dir()?
+                .join(profile_dir)
+                .join(self.profile_icon?),
+        )
+    }
+
+    /// Finds the shortcut of a web app, whose icon is the icon of the app.
+    pub fn find_web_app_lnk(&self, profile: &str, app_id: &str) -> Option<PathBuf> {
+        let profile_dir = pwa_map_profile_dir(profile);
+        let web_apps_dir = self
+            .user_data_dir()?
+            .join(&profile_dir)
+            .join("Web Applications");
+        if !web_apps_dir.is_dir() {
+            return None;
+        }
+        for entry in std::fs::read_dir(&web_apps_dir).ok()?.flatten() {
+            if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                continue;
+            }
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            let dir_app_id = dir_name.strip_prefix("_crx_").unwrap_or(&dir_name);
+            if is_app_id_match(dir_app_id, app_id) {
+                for lnk_entry in std::fs::read_dir(entry.path()).ok()?.flatten() {
+                    let path = lnk_entry.path();
+                    if path.extension().map(|e| e.to_string_lossy().to_lowercase())
+                        == Some("lnk".into())
+                    {
+                        return Some(path);
+                    }
+                }
+                return None;
+            }
+        }
+        None
+    }
+}
+
+/// Parses `<base>` and `<base>.UserData.<profile>` for profiles, `..._crx_<app_id>` and
+/// `..._crx_<app_id>.UserData.<profile>` for web apps and `<package>!App` for packaged web apps.
+fn parse_chromium_aumid<'a>(base: &str, aumid: &'a str) -> BrowserApp<'a> {
+    let crx_prefix = "_crx_";
+    if let Some(idx) = aumid.find(crx_prefix) {
+        let after_crx = &aumid[idx + crx_prefix.len()..];
+        let (app_id, profile) = after_crx
+            .split_once(".UserData.")
+            .unwrap_or((after_crx, "Default"));
+        if app_id.is_empty() || profile.is_empty() {
+            return BrowserApp::Default;
+        }
+        return BrowserApp::WebApp { profile, app_id };
+    }
+    if let Some(package) = aumid.strip_suffix("!App") {
+        if package.is_empty() {
+            return BrowserApp::Default;
+        }
+        return BrowserApp::Appx(package);
+    }
+    match aumid
+        .strip_prefix(base)
+        .and_then(|v| v.strip_prefix(".UserData."))
+    {
+        Some(profile) if !profile.is_empty() => BrowserApp::Profile(profile),
+        _ => BrowserApp::Default,
+    }
+}
+
+/// Parses `<hash>` for the default profile, `<hash>;PrivateBrowsingAUMID` for private windows,
+/// AUMIDs with a `TaskbarTab` part for taskbar tabs and `<hash>;<profile>` for other profiles.
+fn parse_firefox_aumid(aumid: &str) -> BrowserApp<'_> {
+    if aumid
+        .split([';', '.'])
+        .any(|v| v.to_ascii_lowercase().starts_with("taskbartab"))
+    {
+        return BrowserApp::TaskbarTab(aumid);
+    }
+    match aumid.split_once(';') {
+        Some((_, "PrivateBrowsingAUMID")) => BrowserApp::Profile("Private"),
+        Some((_, profile)) if !profile.is_empty() => BrowserApp::Profile(profile),
+        _ => BrowserApp::Default,
+    }
+}
+
+/// Maps the profile of an AUMID, e.g. `Profile1`, to its folder, e.g. `Profile 1`.
+fn pwa_map_profile_dir(aumid_profile: &str) -> String {
+    if let Some(num) = aumid_profile.strip_prefix("Profile") {
+        if num.chars().all(|c| c.is_ascii_digit()) {
+            return format!("Profile {}", num);
+        }
+    }
+    aumid_profile.to_string()
+}
+
+/// Whether the app id of an AUMID, which may be truncated, is the one of the folder.
+fn is_app_id_match(full: &str, truncated: &str) -> bool {
+    if full.eq_ignore_ascii_case(truncated) {
+        return true;
+    }
+    let mut chars = full.chars();
+    for tc in truncated.chars() {
+        loop {
+            match chars.next() {
+                Some(fc) if fc == tc => break,
+                Some(_) => continue,
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_browser() {
+        let browser =
+            find_browser(r"C:\Program Files\BraveSoftware\Brave-Browser\Application\brave.exe");
+        assert_eq!(browser.map(|v| v.exe), Some("brave.exe"));
+        assert!(find_browser(r"C:\Program Files\Google\Chrome\Application\CHROME.EXE").is_some());
+        assert!(find_browser(r"C:\Windows\notepad.exe").is_none());
+    }
+
+    #[test]
+    fn test_parse_chromium_aumid() {
+        assert_eq!(
+            parse_chromium_aumid("Chrome", "Chrome"),
+            BrowserApp::Default
+        );
+        assert_eq!(parse_chromium_aumid("Chrome", ""), BrowserApp::Default);
+        assert_eq!(
+            parse_chromium_aumid("Brave", "Brave.UserData.Profile1"),
+            BrowserApp::Profile("Profile1")
+        );
+        assert_eq!(
+            parse_chromium_aumid("Chrome", "Chrome._crx_abcdef"),
+            BrowserApp::WebApp {
+                profile: "Default",
+                app_id: "abcdef"
+            }
+        );
+        assert_eq!(
+            parse_chromium_aumid("Chrome", "Chrome._crx_abcdef.UserData.Profile2"),
+            BrowserApp::WebApp {
+                profile: "Profile2",
+                app_id: "abcdef"
+            }
+        );
+        assert_eq!(
+            parse_chromium_aumid("MSEdge", "Outlook_8wekyb3d8bbwe!App"),
+            BrowserApp::Appx("Outlook_8wekyb3d8bbwe")
+        );
+        assert_eq!(parse_chromium_aumid("MSEdge", "!App"), BrowserApp::Default);
+        assert_eq!(
+            parse_chromium_aumid("Vivaldi", "Chrome.UserData.Profile1"),
+            BrowserApp::Default
+        );
+    }
+
+    #[test]
+    fn test_parse_firefox_aumid() {
+        assert_eq!(parse_firefox_aumid("308046B0AF4A39CB"), BrowserApp::Default);
+        assert_eq!(
+            parse_firefox_aumid("308046B0AF4A39CB;PrivateBrowsingAUMID"),
+            BrowserApp::Profile("Private")
+        );
+        assert_eq!(
+            parse_firefox_aumid("308046B0AF4A39CB;work"),
+            BrowserApp::Profile("work")
+        );
+        assert_eq!(
+            parse_firefox_aumid("Mozilla.Firefox.TaskbarTab.1234"),
+            BrowserApp::TaskbarTab("Mozilla.Firefox.TaskbarTab.1234")
+        );
+    }
+
+    #[test]
+    fn test_app_key() {
+        let chrome = r"C:\Program Files\Google\Chrome\Application\chrome.exe";
+        let browser = find_browser(chrome).unwrap();
+        assert_eq!(browser.app_key(chrome, "Chrome"), chrome);
+        assert_eq!(
+            browser.app_key(chrome, "Chrome._crx_abcdef"),
+            format!("{chrome}::Default::abcdef")
+        );
+        let firefox = r"C:\Program Files\Mozilla Firefox\firefox.exe";
+        let browser = find_browser(firefox).unwrap();
+        assert_eq!(
+            browser.app_key(firefox, "308046B0AF4A39CB;PrivateBrowsingAUMID"),
+            format!("{firefox}::Private")
+        );
+    }
+
+    #[test]
+    fn test_map_profile_dir() {
+        assert_eq!(pwa_map_profile_dir("Profile12"), "Profile 12");
+        assert_eq!(pwa_map_profile_dir("Default"), "Default");
+        assert!(is_app_id_match("abcdefgh", "acfh"));
+        assert!(!is_app_id_match("abc", "abd"));
+    }
+}
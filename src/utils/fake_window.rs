@@ -0,0 +1,311 @@
+use super::WindowSystem;
+
+use crate::win32::{HICON, HMONITOR, HWND};
+use anyhow::Result;
+use indexmap::IndexMap;
+use std::cell::RefCell;
+
+/// A window living in a [`FakeWindowSystem`].
+#[derive(Debug, Clone)]
+pub struct FakeWindow {
+    pub hwnd: HWND,
+    pub pid: u32,
+    pub module_path: String,
+    pub title: String,
+    pub class: String,
+    pub product_name: Option<String>,
+    pub aumid: Option<String>,
+    pub owner: HWND,
+    pub monitor: isize,
+    pub cloak_type: u32,
+    pub is_visible: bool,
+    pub is_iconic: bool,
+    pub is_tool: bool,
+    pub is_topmost: bool,
+    pub is_small: bool,
+    pub is_elevated: bool,
+}
+
+impl FakeWindow {
+    /// Creates a visible, normal window. Windows with the same `module_path` share a pid.
+    pub fn new(id: isize, module_path: &str, title: &str) -> Self {
+        Self {
+            hwnd: HWND(id as _),
+            pid: 0,
+            module_path: module_path.to_string(),
+            title: title.to_string(),
+            class: String::new(),
+            product_name: None,
+            aumid: None,
+            owner: HWND::default(),
+            monitor: 1,
+            cloak_type: 0,
+            is_visible: true,
+            is_iconic: false,
+            is_tool: false,
+            is_topmost: false,
+            is_small: false,
+            is_elevated: false,
+        }
+    }
+
+    pub fn visible(mut self, value: bool) -> Self {
+        self.is_visible = value;
+        self
+    }
+
+    pub fn iconic(mut self, value: bool) -> Self {
+        self.is_iconic = value;
+        self
+    }
+
+    pub fn tool(mut self, value: bool) -> Self {
+        self.is_tool = value;
+        self
+    }
+
+    pub fn topmost(mut self, value: bool) -> Self {
+        self.is_topmost = value;
+        self
+    }
+
+    pub fn small(mut self, value: bool) -> Self {
+        self.is_small = value;
+        self
+    }
+
+    pub fn elevated(mut self, value: bool) -> Self {
+        self.is_elevated = value;
+        self
+    }
+
+    pub fn cloaked(mut self, cloak_type: u32) -> Self {
+        self.cloak_type = cloak_type;
+        self
+    }
+
+    /// Puts the window on another monitor, all windows start on monitor 1 where the cursor is.
+    pub fn monitor(mut self, monitor: isize) -> Self {
+        self.monitor = monitor;
+        self
+    }
+
+    /// Puts the window in the process `pid`, instead of the one shared by its `module_path`.
+    pub fn pid(mut self, pid: u32) -> Self {
+        self.pid = pid;
+        self
+    }
+
+    pub fn owner(mut self, owner: isize) -> Self {
+        self.owner = HWND(owner as _);
+        self
+    }
+
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = class.to_string();
+        self
+    }
+
+    pub fn aumid(mut self, aumid: &str) -> Self {
+        self.aumid = Some(aumid.to_string());
+        self
+    }
+
+    pub fn product_name(mut self, product_name: &str) -> Self {
+        self.product_name = Some(product_name.to_string());
+        self
+    }
+}
+
+/// An in-memory [`WindowSystem`] for exercising the switcher without a desktop.
+///
+/// Windows are kept in Z-order; the first one is the foreground window.
+#[derive(Debug, Default)]
+pub struct FakeWindowSystem {
+    windows: RefCell<Vec<FakeWindow>>,
+    launched: RefCell<Vec<String>>,
+}
+
+impl FakeWindowSystem {
+    pub fn new(windows: Vec<FakeWindow>) -> Self {
+        let mut pids: Vec<String> = vec![];
+        let windows = windows
+            .into_iter()
+            .map(|mut window| {
+                if window.pid == 0 {
+                    window.pid = match pids.iter().position(|v| v == &window.module_path) {
+                        Some(i) => i as u32 + 1,
+                        None => {
+                            pids.push(window.module_path.clone());
+                            pids.len() as u32
+                        }
+                    };
+                }
+                window
+            })
+            .collect();
+        Self {
+            windows: RefCell::new(windows),
+            launched: Default::default(),
+        }
+    }
+
+    pub fn foreground(&self) -> HWND {
+        self.get_foreground_window()
+    }
+
+    /// The paths passed to [`WindowSystem::launch`], oldest first.
+    pub fn launched(&self) -> Vec<String> {
+        self.launched.borrow().clone()
+    }
+
+    fn with_window<T: Default>(&self, hwnd: HWND, f: impl FnOnce(&FakeWindow) -> T) -> T {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|v| v.hwnd == hwnd)
+            .map(f)
+            .unwrap_or_default()
+    }
+
+    fn with_pid<T: Default>(&self, pid: u32, f: impl FnOnce(&FakeWindow) -> T) -> T {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|v| v.pid == pid)
+            .map(f)
+            .unwrap_or_default()
+    }
+}
+
+impl WindowSystem for FakeWindowSystem {
+    fn enum_windows(&self) -> Result<Vec<HWND>> {
+        Ok(self.windows.borrow().iter().map(|v| v.hwnd).collect())
+    }
+
+    fn get_window_state(&self, hwnd: HWND) -> (bool, bool, bool, bool) {
+        self.with_window(hwnd, |v| {
+            (v.is_visible, v.is_iconic, v.is_tool, v.is_topmost)
+        })
+    }
+
+    fn get_window_cloak_type(&self, hwnd: HWND) -> u32 {
+        self.with_window(hwnd, |v| v.cloak_type)
+    }
+
+    fn is_small_window(&self, hwnd: HWND) -> bool {
+        self.with_window(hwnd, |v| v.is_small)
+    }
+
+    fn is_iconic_window(&self, hwnd: HWND) -> bool {
+        self.with_window(hwnd, |v| v.is_iconic)
+    }
+
+    fn get_owner_window(&self, hwnd: HWND) -> HWND {
+        self.with_window(hwnd, |v| v.owner)
+    }
+
+    fn get_window_pid(&self, hwnd: HWND) -> u32 {
+        self.with_window(hwnd, |v| v.pid)
+    }
+
+    fn get_module_path(&self, pid: u32) -> Option<String> {
+        self.with_pid(pid, |v| Some(v.module_path.clone()))
+    }
+
+    fn is_process_elevated(&self, pid: u32) -> Option<bool> {
+        self.with_pid(pid, |v| Some(v.is_elevated))
+    }
+
+    fn get_window_title(&self, hwnd: HWND) -> String {
+        self.with_window(hwnd, |v| v.title.clone())
+    }
+
+    fn get_window_class(&self, hwnd: HWND) -> String {
+        self.with_window(hwnd, |v| v.class.clone())
+    }
+
+    fn get_aumid(&self, hwnd: HWND) -> Option<String> {
+        self.with_window(hwnd, |v| v.aumid.clone())
+    }
+
+    fn get_product_name(&self, module_path: &str) -> Option<String> {
+        self.windows
+            .borrow()
+            .iter()
+            .find(|v| v.module_path == module_path)
+            .and_then(|v| v.product_name.clone())
+    }
+
+    fn get_foreground_window(&self) -> HWND {
+        self.windows
+            .borrow()
+            .first()
+            .map(|v| v.hwnd)
+            .unwrap_or_default()
+    }
+
+    fn get_window_monitor(&self, hwnd: HWND) -> HMONITOR {
+        HMONITOR(self.with_window(hwnd, |v| v.monitor) as _)
+    }
+
+    fn get_cursor_monitor(&self) -> HMONITOR {
+        HMONITOR(1 as _)
+    }
+
+    fn set_foreground_window(&self, hwnd: HWND) {
+        let mut windows = self.windows.borrow_mut();
+        if let Some(i) = windows.iter().position(|v| v.hwnd == hwnd) {
+            let mut window = windows.remove(i);
+            window.is_iconic = false;
+            windows.insert(0, window);
+        }
+    }
+
+    fn close_window(&self, hwnd: HWND) {
+        self.windows.borrow_mut().retain(|v| v.hwnd != hwnd);
+    }
+
+    fn minimize_window(&self, hwnd: HWND) {
+        if let Some(window) = self
+            .windows
+            .borrow_mut()
+            .iter_mut()
+            .find(|v| v.hwnd == hwnd)
+        {
+            window.is_iconic = true;
+        }
+    }
+
+    fn restore_window(&self, hwnd: HWND) {
+        if let Some(window) = self
+            .windows
+            .borrow_mut()
+            .iter_mut()
+            .find(|v| v.hwnd == hwnd)
+        {
+            window.is_iconic = false;
+        }
+    }
+
+    fn kill_process(&self, pid: u32) -> Result<()> {
+        self.windows.borrow_mut().retain(|v| v.pid != pid);
+        Ok(())
+    }
+
+    fn launch(&self, path: &str) -> Result<()> {
+        self.launched.borrow_mut().push(path.to_string());
+        Ok(())
+    }
+
+    fn get_app_icon(
+        &self,
+        _override_icons: &IndexMap<String, String>,
+        _module_path: &str,
+        hwnd: HWND,
+    ) -> HICON {
+        HICON(hwnd.0)
+    }
+
+    fn destroy_icon(&self, _icon: HICON) {}
+}
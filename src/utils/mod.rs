@@ -1,25 +1,64 @@
+#[cfg(windows)]
 mod admin;
+#[cfg(windows)]
 mod app_icon;
+mod browser;
+#[cfg(windows)]
 mod check_error;
+#[cfg(test)]
+mod fake_window;
+#[cfg(windows)]
 mod handle_wrapper;
+#[cfg(windows)]
 mod regedit;
+#[cfg(windows)]
 mod scheduled_task;
+#[cfg(windows)]
 mod single_instance;
+#[cfg(windows)]
 mod window;
+mod window_system;
+#[cfg(windows)]
 mod windows_theme;
+#[cfg(windows)]
 mod windows_version;
 
+#[cfg(windows)]
 pub use admin::*;
+#[cfg(windows)]
 pub use app_icon::*;
+pub use browser::*;
+#[cfg(windows)]
 pub use check_error::*;
+#[cfg(test)]
+pub use fake_window::*;
+#[cfg(windows)]
 pub use handle_wrapper::*;
+#[cfg(windows)]
 pub use regedit::*;
+#[cfg(windows)]
 pub use scheduled_task::*;
+#[cfg(windows)]
 pub use single_instance::*;
+#[cfg(windows)]
 pub use window::*;
+pub use window_system::*;
+#[cfg(windows)]
 pub use windows_theme::*;
+#[cfg(windows)]
 pub use windows_version::*;
 
+use anyhow::{anyhow, Result};
+use std::path::PathBuf;
+
+pub fn get_exe_folder() -> Result<PathBuf> {
+    let path =
+        std::env::current_exe().map_err(|err| anyhow!("Failed to get binary path, {err}"))?;
+    path.parent()
+        .ok_or_else(|| anyhow!("Failed to get binary folder"))
+        .map(|v| v.to_path_buf())
+}
+
 pub fn to_wstring(value: &str) -> Vec<u16> {
     value.encode_utf16().chain(Some(0)).collect::<Vec<u16>>()
 }
@@ -1,34 +1,45 @@
-use crate::utils::is_process_elevated;
+use crate::utils::{get_app_icon, is_process_elevated, to_wstring, HandleWrapper, WindowSystem};
 
 use anyhow::{anyhow, Result};
 use indexmap::IndexMap;
-use std::{ffi::c_void, mem::size_of, path::PathBuf};
-use windows::core::{BOOL, PCWSTR, PWSTR};
+use std::{ffi::c_void, mem::size_of};
+use windows::core::{w, BOOL, PCWSTR, PWSTR};
 use windows::Win32::{
-    Foundation::{ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS, HWND, LPARAM, MAX_PATH, POINT, RECT},
+    Foundation::{
+        ERROR_INSUFFICIENT_BUFFER, ERROR_SUCCESS, HWND, LPARAM, MAX_PATH, POINT, RECT, WPARAM,
+    },
     Graphics::{
-        Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED, DWM_CLOAKED_SHELL},
-        Gdi::{GetMonitorInfoW, MonitorFromPoint, MONITORINFO, MONITOR_DEFAULTTONEAREST},
+        Dwm::{DwmGetWindowAttribute, DWMWA_CLOAKED},
+        Gdi::{
+            GetMonitorInfoW, MonitorFromPoint, MonitorFromWindow, HMONITOR, MONITORINFO,
+            MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTOPRIMARY,
+        },
     },
     Storage::{
         EnhancedStorage::PKEY_AppUserModel_ID,
+        FileSystem::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW},
         Packaging::Appx::{GetPackagePathByFullName, GetPackagesByPackageFamily},
     },
     System::{
         LibraryLoader::GetModuleFileNameW,
         Threading::{
-            OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
-            PROCESS_QUERY_LIMITED_INFORMATION,
+            OpenProcess, QueryFullProcessImageNameW, TerminateProcess, PROCESS_NAME_WIN32,
+            PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
         },
     },
     UI::{
+        HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI},
         Input::KeyboardAndMouse::{SendInput, INPUT, INPUT_MOUSE},
-        Shell::PropertiesSystem::{IPropertyStore, SHGetPropertyStoreForWindow},
+        Shell::{
+            PropertiesSystem::{IPropertyStore, SHGetPropertyStoreForWindow},
+            ShellExecuteW,
+        },
         WindowsAndMessaging::{
-            EnumWindows, GetCursorPos, GetForegroundWindow, GetWindow, GetWindowLongPtrW,
-            GetWindowPlacement, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
-            SetForegroundWindow, ShowWindow, GWL_EXSTYLE, GWL_STYLE, GWL_USERDATA, GW_OWNER,
-            SW_RESTORE, WINDOWPLACEMENT, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_ICONIC, WS_VISIBLE,
+            DestroyIcon, EnumWindows, GetClassNameW, GetCursorPos, GetForegroundWindow, GetWindow,
+            GetWindowLongPtrW, GetWindowPlacement, GetWindowTextW, GetWindowThreadProcessId,
+            IsIconic, PostMessageW, SetForegroundWindow, ShowWindow, GWL_EXSTYLE, GWL_STYLE,
+            GWL_USERDATA, GW_OWNER, HICON, SW_RESTORE, SW_SHOWMINNOACTIVE, SW_SHOWNORMAL,
+            WINDOWPLACEMENT, WM_CLOSE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_ICONIC, WS_VISIBLE,
         },
     },
 };
@@ -62,39 +73,51 @@ pub fn get_window_cloak_type(hwnd: HWND) -> u32 {
     cloak_type
 }
 
-fn is_cloaked_window(hwnd: HWND, only_current_desktop: bool) -> bool {
-    let cloak_type = get_window_cloak_type(hwnd);
+pub fn is_small_window(hwnd: HWND) -> bool {
+    let (width, height) = get_window_size(hwnd);
+    width < 120 || height < 90
+}
 
-    if only_current_desktop {
-        // Any kind of cloaking counts against a window
-        cloak_type != 0
-    } else {
-        // Windows from other desktops will be cloaked as SHELL, so we treat them
-        // as if they are uncloaked. All other cloak types count against the window
-        cloak_type | DWM_CLOAKED_SHELL != DWM_CLOAKED_SHELL
+pub fn get_cursor_monitor() -> HMONITOR {
+    unsafe {
+        let mut cursor = POINT::default();
+        let _ = GetCursorPos(&mut cursor);
+        MonitorFromPoint(cursor, MONITOR_DEFAULTTONEAREST)
     }
 }
 
-pub fn is_small_window(hwnd: HWND) -> bool {
-    let (width, height) = get_window_size(hwnd);
-    width < 120 || height < 90
+pub fn get_primary_monitor() -> HMONITOR {
+    unsafe { MonitorFromPoint(POINT::default(), MONITOR_DEFAULTTOPRIMARY) }
+}
+
+pub fn get_point_monitor(x: i32, y: i32) -> HMONITOR {
+    unsafe { MonitorFromPoint(POINT { x, y }, MONITOR_DEFAULTTONEAREST) }
 }
 
-pub fn get_moinitor_rect() -> RECT {
+pub fn get_window_monitor(hwnd: HWND) -> HMONITOR {
+    unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) }
+}
+
+pub fn get_monitor_rect(hmonitor: HMONITOR) -> RECT {
     unsafe {
         let mut mi = MONITORINFO {
             cbSize: std::mem::size_of::<MONITORINFO>() as u32,
             ..MONITORINFO::default()
         };
-        let mut cursor = POINT::default();
-        let _ = GetCursorPos(&mut cursor);
-
-        let hmonitor = MonitorFromPoint(cursor, MONITOR_DEFAULTTONEAREST);
         let _ = GetMonitorInfoW(hmonitor, &mut mi);
         mi.rcMonitor
     }
 }
 
+/// The scale of the monitor relative to 96 DPI, e.g. 1.5 at 150%.
+pub fn get_monitor_dpi_scale(hmonitor: HMONITOR) -> f64 {
+    let (mut dpi_x, mut dpi_y) = (0, 0);
+    match unsafe { GetDpiForMonitor(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) } {
+        Ok(()) if dpi_x > 0 => dpi_x as f64 / 96.0,
+        _ => 1.0,
+    }
+}
+
 pub fn get_window_size(hwnd: HWND) -> (i32, i32) {
     let mut placement = WINDOWPLACEMENT::default();
     let _ = unsafe { GetWindowPlacement(hwnd, &mut placement) };
@@ -102,14 +125,6 @@ pub fn get_window_size(hwnd: HWND) -> (i32, i32) {
     ((rect.right - rect.left), (rect.bottom - rect.top))
 }
 
-pub fn get_exe_folder() -> Result<PathBuf> {
-    let path =
-        std::env::current_exe().map_err(|err| anyhow!("Failed to get binary path, {err}"))?;
-    path.parent()
-        .ok_or_else(|| anyhow!("Failed to get binary folder"))
-        .map(|v| v.to_path_buf())
-}
-
 pub fn get_exe_path() -> Vec<u16> {
     let mut path = vec![0u16; MAX_PATH as _];
     let size = unsafe { GetModuleFileNameW(None, &mut path) } as usize;
@@ -145,144 +160,12 @@ pub fn get_module_path(pid: u32) -> Option<String> {
     Some(module_path)
 }
 
-fn is_chrome_browser(module_path: &str) -> bool {
-    let lower = module_path.to_lowercase();
-    lower.ends_with("chrome.exe")
-}
-
-fn is_edge_browser(module_path: &str) -> bool {
-    let lower = module_path.to_lowercase();
-    lower.ends_with("msedge.exe")
-}
-
-fn get_aumid(hwnd: HWND) -> Option<String> {
+pub fn get_aumid(hwnd: HWND) -> Option<String> {
     let store: IPropertyStore = unsafe { SHGetPropertyStoreForWindow(hwnd).ok()? };
     let propvar = unsafe { store.GetValue(&PKEY_AppUserModel_ID).ok()? };
     Some(propvar.to_string())
 }
 
-fn get_edge_aumid_info(hwnd: HWND) -> (Option<String>, Option<String>) {
-    let aumid = match get_aumid(hwnd) {
-        Some(v) => v,
-        None => return (None, None),
-    };
-
-    if let Some(pkg) = aumid.strip_suffix("!App") {
-        if !pkg.is_empty() {
-            return (None, Some(pkg.to_string()));
-        }
-        return (None, None);
-    }
-
-    if aumid == "MSEdge" || aumid.is_empty() {
-        return (None, None);
-    }
-    if let Some(profile) = aumid.strip_prefix("MSEdge.UserData.") {
-        if !profile.is_empty() {
-            return (Some(profile.to_string()), None);
-        }
-    }
-    (None, None)
-}
-
-fn get_chrome_aumid_info(hwnd: HWND) -> (Option<String>, Option<String>) {
-    let aumid = match get_aumid(hwnd) {
-        Some(v) => v,
-        None => return (None, None),
-    };
-
-    let crx_prefix = "_crx_";
-    if let Some(idx) = aumid.find(crx_prefix) {
-        let after_crx = &aumid[idx + crx_prefix.len()..];
-        let (app_id, profile) = if let Some(dot_idx) = after_crx.find(".UserData.") {
-            (
-                &after_crx[..dot_idx],
-                &after_crx[dot_idx + ".UserData.".len()..],
-            )
-        } else {
-            (after_crx, "Default")
-        };
-        if !app_id.is_empty() && !profile.is_empty() {
-            let profile = if profile == "Default" {
-                None
-            } else {
-                Some(profile.to_string())
-            };
-            return (profile, Some(app_id.to_string()));
-        }
-        return (None, None);
-    }
-
-    if aumid == "Chrome" || aumid.is_empty() {
-        return (None, None);
-    }
-    if let Some(profile) = aumid.strip_prefix("Chrome.UserData.") {
-        if !profile.is_empty() {
-            return (Some(profile.to_string()), None);
-        }
-    }
-    (None, None)
-}
-
-fn is_app_id_match(full: &str, truncated: &str) -> bool {
-    if full.eq_ignore_ascii_case(truncated) {
-        return true;
-    }
-    let mut chars = full.chars();
-    for tc in truncated.chars() {
-        loop {
-            match chars.next() {
-                Some(fc) if fc == tc => break,
-                Some(_) => continue,
-                None => return false,
-            }
-        }
-    }
-    true
-}
-
-pub(crate) fn pwa_map_profile_dir(aumid_profile: &str) -> String {
-    if let Some(num) = aumid_profile.strip_prefix("Profile") {
-        if num.chars().all(|c| c.is_ascii_digit()) {
-            return format!("Profile {}", num);
-        }
-    }
-    aumid_profile.to_string()
-}
-
-pub(crate) fn pwa_find_lnk_path(
-    user_data_dir: &str,
-    profile: &str,
-    app_id: &str,
-) -> Option<PathBuf> {
-    let profile_dir = pwa_map_profile_dir(profile);
-    let web_apps_dir = PathBuf::from(user_data_dir)
-        .join(&profile_dir)
-        .join("Web Applications");
-    if !web_apps_dir.is_dir() {
-        return None;
-    }
-    for entry in std::fs::read_dir(&web_apps_dir).ok()?.flatten() {
-        if !entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
-            continue;
-        }
-        let dir_name = entry.file_name().to_string_lossy().to_string();
-        let dir_app_id = dir_name.strip_prefix("_crx_").unwrap_or(&dir_name);
-        if is_app_id_match(dir_app_id, app_id) {
-            for lnk_entry in std::fs::read_dir(entry.path()).ok()?.flatten() {
-                let path = lnk_entry.path();
-                if path.extension().map(|e| e.to_string_lossy().to_lowercase())
-                    == Some("lnk".into())
-                {
-                    return Some(path);
-                }
-            }
-            return None;
-        }
-    }
-    None
-}
-
 pub(crate) fn find_appx_pkg_dir(package_family_name: &str) -> Option<String> {
     unsafe {
         let pfn: Vec<u16> = package_family_name.encode_utf16().chain(Some(0)).collect();
@@ -344,23 +227,6 @@ pub(crate) fn find_appx_pkg_dir(package_family_name: &str) -> Option<String> {
     None
 }
 
-pub(crate) fn get_default_user_data_dir(module_path: &str) -> Option<String> {
-    let local_app_data = std::env::var("LOCALAPPDATA").ok()?;
-    let browser_path = if module_path.to_lowercase().contains("chrome.exe") {
-        r"Google\Chrome\User Data"
-    } else if module_path.to_lowercase().contains("msedge.exe") {
-        r"Microsoft\Edge\User Data"
-    } else {
-        return None;
-    };
-    Some(
-        PathBuf::from(local_app_data)
-            .join(browser_path)
-            .to_string_lossy()
-            .to_string(),
-    )
-}
-
 pub fn get_window_exe(hwnd: HWND) -> Option<String> {
     let pid = get_window_pid(hwnd);
     if pid == 0 {
@@ -370,6 +236,84 @@ pub fn get_window_exe(hwnd: HWND) -> Option<String> {
     module_path.split('\\').map(|v| v.to_string()).next_back()
 }
 
+pub fn kill_process(pid: u32) -> Result<()> {
+    let handle = unsafe { OpenProcess(PROCESS_TERMINATE, false, pid) }
+        .map_err(|err| anyhow!("Failed to open process {pid}, {err}"))?;
+    let handle = HandleWrapper::new(handle);
+    unsafe { TerminateProcess(handle.get_handle(), 1) }
+        .map_err(|err| anyhow!("Failed to terminate process {pid}, {err}"))
+}
+
+/// Opens a program, or anything else the shell can open such as `shell:AppsFolder\<aumid>`.
+pub fn shell_open(path: &str) -> Result<()> {
+    let file = to_wstring(path);
+    let ret = unsafe {
+        ShellExecuteW(
+            None,
+            w!("open"),
+            PCWSTR(file.as_ptr()),
+            None,
+            None,
+            SW_SHOWNORMAL,
+        )
+    };
+    // values up to 32 are error codes
+    if ret.0 as isize <= 32 {
+        return Err(anyhow!(
+            "Failed to open {path}, {}",
+            windows::core::Error::from_win32()
+        ));
+    }
+    Ok(())
+}
+
+/// Reads the product name, or the file description, from the version resource of the module.
+pub fn get_product_name(module_path: &str) -> Option<String> {
+    let path = to_wstring(module_path);
+    let size = unsafe { GetFileVersionInfoSizeW(PCWSTR(path.as_ptr()), None) };
+    if size == 0 {
+        return None;
+    }
+    let mut data = vec![0u8; size as usize];
+    unsafe {
+        GetFileVersionInfoW(
+            PCWSTR(path.as_ptr()),
+            None,
+            size,
+            data.as_mut_ptr() as *mut c_void,
+        )
+    }
+    .ok()?;
+    let query = |name: &str| -> Option<(*mut c_void, u32)> {
+        let name = to_wstring(name);
+        let mut buffer: *mut c_void = std::ptr::null_mut();
+        let mut len: u32 = 0;
+        let ret = unsafe {
+            VerQueryValueW(
+                data.as_ptr() as *const c_void,
+                PCWSTR(name.as_ptr()),
+                &mut buffer,
+                &mut len,
+            )
+        };
+        (ret.as_bool() && !buffer.is_null() && len > 0).then_some((buffer, len))
+    };
+    let (buffer, len) = query(r"\VarFileInfo\Translation")?;
+    if (len as usize) < size_of::<[u16; 2]>() {
+        return None;
+    }
+    let [lang, codepage] = unsafe { *(buffer as *const [u16; 2]) };
+    ["ProductName", "FileDescription"].iter().find_map(|key| {
+        let (buffer, len) = query(&format!(r"\StringFileInfo\{lang:04x}{codepage:04x}\{key}"))?;
+        let value = unsafe { std::slice::from_raw_parts(buffer as *const u16, len as usize) };
+        let value = String::from_utf16_lossy(value)
+            .trim_end_matches('\0')
+            .trim()
+            .to_string();
+        (!value.is_empty()).then_some(value)
+    })
+}
+
 pub fn set_foreground_window(hwnd: HWND) {
     // ref https://github.com/microsoft/PowerToys/blob/4cb72ee126caf1f720c507f6a1dbe658cd515366/src/modules/fancyzones/FancyZonesLib/WindowUtils.cpp#L191
     unsafe {
@@ -401,6 +345,15 @@ pub fn get_window_title(hwnd: HWND) -> String {
     String::from_utf16_lossy(&buf[..len as usize])
 }
 
+pub fn get_window_class(hwnd: HWND) -> String {
+    let mut buf = [0u16; 256];
+    let len = unsafe { GetClassNameW(hwnd, buf.as_mut_slice()) };
+    if len == 0 {
+        return String::new();
+    }
+    String::from_utf16_lossy(&buf[..len as usize])
+}
+
 pub fn get_owner_window(hwnd: HWND) -> HWND {
     unsafe { GetWindow(hwnd, GW_OWNER) }.unwrap_or_default()
 }
@@ -425,79 +378,116 @@ pub fn set_window_user_data(hwnd: HWND, ptr: isize) -> isize {
     unsafe { windows::Win32::UI::WindowsAndMessaging::SetWindowLongPtrW(hwnd, GWL_USERDATA, ptr) }
 }
 
-/// Lists available windows
-///
-/// Duo to the limitation of `OpenProcess`, this function will not list `Task Manager`
-/// and others which are running as administrator if `Switcher` is not `running as administrator`.
-pub fn list_windows(
-    ignore_minimal: bool,
-    only_current_desktop: bool,
-    is_admin: bool,
-) -> Result<IndexMap<String, Vec<(HWND, String)>>> {
-    let mut result: IndexMap<String, Vec<(HWND, String)>> = IndexMap::new();
-    let mut hwnds: Vec<HWND> = Default::default();
-    unsafe { EnumWindows(Some(enum_window), LPARAM(&mut hwnds as *mut _ as isize)) }
-        .map_err(|e| anyhow!("Fail to get windows {}", e))?;
-    let mut valid_hwnds = vec![];
-    let mut owner_hwnds = vec![];
-    for hwnd in hwnds.iter().cloned() {
-        let (is_visible, is_iconic, is_tool, is_topmost) = get_window_state(hwnd);
-        let ok = is_visible
-            && (if ignore_minimal { !is_iconic } else { true })
-            && !is_tool
-            && !is_topmost
-            && !is_cloaked_window(hwnd, only_current_desktop)
-            && !is_small_window(hwnd);
-        if ok {
-            let title = get_window_title(hwnd);
-            if !title.is_empty() && title != "Windows Input Experience" {
-                valid_hwnds.push((hwnd, title));
-            }
-        }
-        owner_hwnds.push(get_owner_window(hwnd))
-    }
-    for (hwnd, title) in valid_hwnds.into_iter() {
-        let mut pid = get_window_pid(hwnd);
-        let mut module_path = get_module_path(pid).unwrap_or_default();
-        if !is_valid_module_path(&module_path) {
-            if let Some((i, _)) = owner_hwnds.iter().enumerate().find(|(_, v)| **v == hwnd) {
-                pid = get_window_pid(hwnds[i]);
-                module_path = get_module_path(pid).unwrap_or_default();
-            }
-        }
-        if is_valid_module_path(&module_path) {
-            if !is_admin {
-                if let Some(true) = is_process_elevated(pid) {
-                    continue;
-                }
-            }
-            let key = if is_chrome_browser(&module_path) {
-                match get_chrome_aumid_info(hwnd) {
-                    (Some(profile), Some(app_id)) => {
-                        format!("{}::{}::{}", module_path, profile, app_id)
-                    }
-                    (Some(profile), None) => format!("{}::{}", module_path, profile),
-                    (None, Some(app_id)) => format!("{}::Default::{}", module_path, app_id),
-                    (None, None) => module_path.clone(),
-                }
-            } else if is_edge_browser(&module_path) {
-                match get_edge_aumid_info(hwnd) {
-                    (None, Some(pkg)) => format!("{}::appx::{}", module_path, pkg),
-                    (Some(profile), None) => format!("{}::{}", module_path, profile),
-                    _ => module_path.clone(),
-                }
-            } else {
-                module_path.clone()
-            };
-            result.entry(key).or_default().push((hwnd, title));
-        }
+/// The [`WindowSystem`] backed by the Win32 API.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Win32WindowSystem;
+
+impl WindowSystem for Win32WindowSystem {
+    fn enum_windows(&self) -> Result<Vec<HWND>> {
+        let mut hwnds: Vec<HWND> = Default::default();
+        unsafe { EnumWindows(Some(enum_window), LPARAM(&mut hwnds as *mut _ as isize)) }
+            .map_err(|e| anyhow!("Fail to get windows {}", e))?;
+        Ok(hwnds)
+    }
+
+    fn get_window_state(&self, hwnd: HWND) -> (bool, bool, bool, bool) {
+        get_window_state(hwnd)
     }
-    debug!("list windows {result:?}");
-    Ok(result)
-}
 
-fn is_valid_module_path(module_path: &str) -> bool {
-    !module_path.is_empty() && module_path != "C:\\Windows\\System32\\ApplicationFrameHost.exe"
+    fn get_window_cloak_type(&self, hwnd: HWND) -> u32 {
+        get_window_cloak_type(hwnd)
+    }
+
+    fn is_small_window(&self, hwnd: HWND) -> bool {
+        is_small_window(hwnd)
+    }
+
+    fn is_iconic_window(&self, hwnd: HWND) -> bool {
+        is_iconic_window(hwnd)
+    }
+
+    fn get_owner_window(&self, hwnd: HWND) -> HWND {
+        get_owner_window(hwnd)
+    }
+
+    fn get_window_pid(&self, hwnd: HWND) -> u32 {
+        get_window_pid(hwnd)
+    }
+
+    fn get_module_path(&self, pid: u32) -> Option<String> {
+        get_module_path(pid)
+    }
+
+    fn is_process_elevated(&self, pid: u32) -> Option<bool> {
+        is_process_elevated(pid)
+    }
+
+    fn get_window_title(&self, hwnd: HWND) -> String {
+        get_window_title(hwnd)
+    }
+
+    fn get_window_class(&self, hwnd: HWND) -> String {
+        get_window_class(hwnd)
+    }
+
+    fn get_aumid(&self, hwnd: HWND) -> Option<String> {
+        get_aumid(hwnd)
+    }
+
+    fn get_product_name(&self, module_path: &str) -> Option<String> {
+        get_product_name(module_path)
+    }
+
+    fn get_foreground_window(&self) -> HWND {
+        get_foreground_window()
+    }
+
+    fn get_window_monitor(&self, hwnd: HWND) -> HMONITOR {
+        get_window_monitor(hwnd)
+    }
+
+    fn get_cursor_monitor(&self) -> HMONITOR {
+        get_cursor_monitor()
+    }
+
+    fn set_foreground_window(&self, hwnd: HWND) {
+        set_foreground_window(hwnd)
+    }
+
+    fn close_window(&self, hwnd: HWND) {
+        let _ = unsafe { PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+    }
+
+    fn minimize_window(&self, hwnd: HWND) {
+        let _ = unsafe { ShowWindow(hwnd, SW_SHOWMINNOACTIVE) };
+    }
+
+    fn restore_window(&self, hwnd: HWND) {
+        let _ = unsafe { ShowWindow(hwnd, SW_RESTORE) };
+    }
+
+    fn kill_process(&self, pid: u32) -> Result<()> {
+        kill_process(pid)
+    }
+
+    fn launch(&self, path: &str) -> Result<()> {
+        shell_open(path)
+    }
+
+    fn get_app_icon(
+        &self,
+        override_icons: &IndexMap<String, String>,
+        module_path: &str,
+        hwnd: HWND,
+    ) -> HICON {
+        get_app_icon(override_icons, module_path, hwnd)
+    }
+
+    fn destroy_icon(&self, icon: HICON) {
+        unsafe {
+            let _ = DestroyIcon(icon);
+        }
+    }
 }
 
 extern "system" fn enum_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
@@ -0,0 +1,135 @@
+use crate::rules::{is_window_included, Grouper, Rule, WindowInfo};
+use crate::utils::find_browser;
+use crate::win32::{HICON, HMONITOR, HWND};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+
+/// `DWM_CLOAKED_SHELL`, the cloak type of windows on other virtual desktops
+const DWM_CLOAKED_SHELL: u32 = 2;
+
+/// Abstracts the window manager queries and commands the switcher relies on,
+/// so that the switching logic can run against something other than a live desktop.
+pub trait WindowSystem {
+    /// Returns all top-level windows in Z-order.
+    fn enum_windows(&self) -> Result<Vec<HWND>>;
+    /// Returns `(is_visible, is_iconic, is_tool, is_topmost)`.
+    fn get_window_state(&self, hwnd: HWND) -> (bool, bool, bool, bool);
+    fn get_window_cloak_type(&self, hwnd: HWND) -> u32;
+    fn is_small_window(&self, hwnd: HWND) -> bool;
+    fn is_iconic_window(&self, hwnd: HWND) -> bool;
+    fn get_owner_window(&self, hwnd: HWND) -> HWND;
+    fn get_window_pid(&self, hwnd: HWND) -> u32;
+    fn get_module_path(&self, pid: u32) -> Option<String>;
+    fn is_process_elevated(&self, pid: u32) -> Option<bool>;
+    fn get_window_title(&self, hwnd: HWND) -> String;
+    fn get_window_class(&self, hwnd: HWND) -> String;
+    fn get_aumid(&self, hwnd: HWND) -> Option<String>;
+    fn get_product_name(&self, module_path: &str) -> Option<String>;
+    fn get_foreground_window(&self) -> HWND;
+    /// The monitor with the largest part of the window, minimized windows use their restored position.
+    fn get_window_monitor(&self, hwnd: HWND) -> HMONITOR;
+    fn get_cursor_monitor(&self) -> HMONITOR;
+    fn set_foreground_window(&self, hwnd: HWND);
+    /// Asks the window to close, like clicking its close button.
+    fn close_window(&self, hwnd: HWND);
+    fn minimize_window(&self, hwnd: HWND);
+    fn restore_window(&self, hwnd: HWND);
+    fn kill_process(&self, pid: u32) -> Result<()>;
+    /// Starts a program, `path` is anything the shell can open.
+    fn launch(&self, path: &str) -> Result<()>;
+    fn get_app_icon(
+        &self,
+        override_icons: &IndexMap<String, String>,
+        module_path: &str,
+        hwnd: HWND,
+    ) -> HICON;
+    fn destroy_icon(&self, icon: HICON);
+}
+
+/// Lists available windows
+///
+/// Duo to the limitation of `OpenProcess`, this function will not list `Task Manager`
+/// and others which are running as administrator if `Switcher` is not `running as administrator`.
+/// If `monitor` is set, only the windows on that monitor are listed. Windows are assigned
+/// to apps by `grouper`.
+pub fn list_windows<W: WindowSystem + ?Sized>(
+    ws: &W,
+    ignore_minimal: bool,
+    only_current_desktop: bool,
+    monitor: Option<HMONITOR>,
+    rules: &[Rule],
+    grouper: &mut Grouper,
+    is_admin: bool,
+) -> Result<IndexMap<String, Vec<(HWND, String)>>> {
+    let mut result: IndexMap<String, Vec<(HWND, String)>> = IndexMap::new();
+    let hwnds = ws.enum_windows()?;
+    let mut valid_hwnds = vec![];
+    let mut owner_hwnds = vec![];
+    for hwnd in hwnds.iter().cloned() {
+        let (is_visible, is_iconic, is_tool, is_topmost) = ws.get_window_state(hwnd);
+        let ok = is_visible
+            && (if ignore_minimal { !is_iconic } else { true })
+            && !is_cloaked_window(ws.get_window_cloak_type(hwnd), only_current_desktop)
+            && !ws.is_small_window(hwnd)
+            && monitor.is_none_or(|v| ws.get_window_monitor(hwnd) == v);
+        if ok {
+            valid_hwnds.push((hwnd, is_iconic, is_tool, is_topmost));
+        }
+        owner_hwnds.push(ws.get_owner_window(hwnd))
+    }
+    for (hwnd, is_iconic, is_tool, is_topmost) in valid_hwnds.into_iter() {
+        let mut pid = ws.get_window_pid(hwnd);
+        let mut module_path = ws.get_module_path(pid).unwrap_or_default();
+        if !is_valid_module_path(&module_path) {
+            if let Some((i, _)) = owner_hwnds.iter().enumerate().find(|(_, v)| **v == hwnd) {
+                pid = ws.get_window_pid(hwnds[i]);
+                module_path = ws.get_module_path(pid).unwrap_or_default();
+            }
+        }
+        if is_valid_module_path(&module_path) {
+            let title = ws.get_window_title(hwnd);
+            let class = ws.get_window_class(hwnd);
+            let info = WindowInfo {
+                exe: &module_path,
+                class: &class,
+                title: &title,
+                is_topmost,
+                is_tool,
+                is_iconic,
+            };
+            if !is_window_included(rules, &info) {
+                continue;
+            }
+            if !is_admin {
+                if let Some(true) = ws.is_process_elevated(pid) {
+                    continue;
+                }
+            }
+            let aumid = || ws.get_aumid(hwnd).unwrap_or_default();
+            let key = match find_browser(&module_path) {
+                Some(browser) => browser.app_key(&module_path, &aumid()),
+                None => module_path.clone(),
+            };
+            let key = grouper.key(&info, aumid, key);
+            result.entry(key).or_default().push((hwnd, title));
+        }
+    }
+    debug!("list windows {result:?}");
+    Ok(result)
+}
+
+fn is_valid_module_path(module_path: &str) -> bool {
+    !module_path.is_empty() && module_path != "C:\\Windows\\System32\\ApplicationFrameHost.exe"
+}
+
+fn is_cloaked_window(cloak_type: u32, only_current_desktop: bool) -> bool {
+    if only_current_desktop {
+        // Any kind of cloaking counts against a window
+        cloak_type != 0
+    } else {
+        // Windows from other desktops will be cloaked as SHELL, so we treat them
+        // as if they are uncloaked. All other cloak types count against the window
+        cloak_type | DWM_CLOAKED_SHELL != DWM_CLOAKED_SHELL
+    }
+}
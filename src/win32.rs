@@ -0,0 +1,57 @@
+//! The Win32 types of the platform-independent modules.
+//!
+//! On Windows they are the types of the `windows` crate. That crate doesn't build on other
+//! platforms, so there they are stand-ins of the same shape, which is enough for the
+//! switching logic and its tests.
+
+#[cfg(windows)]
+pub use windows::Win32::{
+    Foundation::{HWND, POINT, RECT},
+    Graphics::Gdi::HMONITOR,
+    UI::WindowsAndMessaging::HICON,
+};
+
+#[cfg(not(windows))]
+pub use stand_ins::*;
+
+#[cfg(not(windows))]
+mod stand_ins {
+    use std::ffi::c_void;
+
+    macro_rules! handle {
+        ($name:ident) => {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+            pub struct $name(pub *mut c_void);
+
+            impl $name {
+                pub fn is_invalid(&self) -> bool {
+                    self.0.is_null()
+                }
+            }
+
+            impl Default for $name {
+                fn default() -> Self {
+                    Self(std::ptr::null_mut())
+                }
+            }
+        };
+    }
+
+    handle!(HWND);
+    handle!(HMONITOR);
+    handle!(HICON);
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct RECT {
+        pub left: i32,
+        pub top: i32,
+        pub right: i32,
+        pub bottom: i32,
+    }
+
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct POINT {
+        pub x: i32,
+        pub y: i32,
+    }
+}
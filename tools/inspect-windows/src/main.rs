@@ -1,11 +1,23 @@
+#[cfg(windows)]
 use anyhow::{Context, Result};
+#[cfg(windows)]
 use window_switcher::utils::*;
 
-use windows::core::BOOL;
-use windows::Win32::Foundation::{HWND, LPARAM};
-use windows::Win32::Graphics::Dwm::{DWM_CLOAKED_APP, DWM_CLOAKED_INHERITED, DWM_CLOAKED_SHELL};
-use windows::Win32::UI::WindowsAndMessaging::{EnumWindows, GetWindow, GW_OWNER};
+#[cfg(windows)]
+use windows::{
+    core::BOOL,
+    Win32::Foundation::{HWND, LPARAM},
+    Win32::Graphics::Dwm::{DWM_CLOAKED_APP, DWM_CLOAKED_INHERITED, DWM_CLOAKED_SHELL},
+    Win32::UI::WindowsAndMessaging::{EnumWindows, GetWindow, GW_OWNER},
+};
 
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("inspect-windows only runs on Windows");
+    std::process::exit(1);
+}
+
+#[cfg(windows)]
 fn main() -> Result<()> {
     let mut hwnds: Vec<HWND> = Default::default();
     unsafe { EnumWindows(Some(enum_window), LPARAM(&mut hwnds as *mut _ as isize)) }
@@ -37,6 +49,7 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+#[cfg(windows)]
 fn pretty_bool(value: bool) -> String {
     if value {
         "*".into()
@@ -45,6 +58,7 @@ fn pretty_bool(value: bool) -> String {
     }
 }
 
+#[cfg(windows)]
 fn pretty_cloak(value: u32) -> &'static str {
     match value {
         0 => " ",
@@ -55,6 +69,7 @@ fn pretty_cloak(value: u32) -> &'static str {
     }
 }
 
+#[cfg(windows)]
 extern "system" fn enum_window(hwnd: HWND, lparam: LPARAM) -> BOOL {
     let windows: &mut Vec<HWND> = unsafe { &mut *(lparam.0 as *mut Vec<HWND>) };
     windows.push(hwnd);